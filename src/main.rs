@@ -13,7 +13,11 @@ extern crate clap;
 mod shifty;
 mod util;
 use std::error;
-use shifty::{Action, Encoding};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+use shifty::{analysis, cipher, Action, Encoding, UnknownChars};
+use shifty::cipher::{Cipher, Keystream};
 use clap::{Arg, ArgMatches, ArgGroup, App, SubCommand};
 
 pub type Result<T> = std::result::Result<T, Box<error::Error>>;
@@ -27,115 +31,768 @@ fn shut_up_dead_code() -> String {
     e.decrypt("foo", "bar").unwrap()
 }
 
+/// The TOML config behind an encoding option such as `--encoding`,
+/// when it points at a file rather than naming a built-in encoding.
+fn encoding_config(cmd: &ArgMatches, arg: &str) -> Result<Option<String>> {
+    match cmd.value_of(arg) {
+        Some(name) if !shifty::BUILTIN_ENCODINGS.contains(&name) && Path::new(name).is_file() => {
+            Ok(Some(try!(util::read_path(name))))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// The encoding named by `arg`, which may be a built-in encoding or
+/// point at a TOML file. Falls back to `default` when it is not given.
+fn encoding_from_arg(cmd: &ArgMatches, arg: &str, default: fn() -> Encoding) -> Result<Encoding> {
+    if let Some(toml) = try!(encoding_config(cmd, arg)) {
+        return Ok(try!(Encoding::parse(&toml)));
+    }
+    match cmd.value_of(arg) {
+        Some(name) => Ok(try!(shifty::encoding_by_name(name))),
+        None => Ok(default()),
+    }
+}
+
+/// Pick the encoding from `--encoding`. Defaults to `alphanumeric_space`.
+fn load_encoding(cmd: &ArgMatches) -> Result<Encoding> {
+    encoding_from_arg(cmd, "encoding", shifty::alphanumeric_space)
+}
+
+/// The encoding digits are written with, from `--digit-encoding`.
+fn load_digit_encoding(cmd: &ArgMatches) -> Result<Encoding> {
+    encoding_from_arg(cmd, "digit_encoding", shifty::digits)
+}
+
+/// Read from `--input`, `--input-file`, or stdin when neither is given
+/// or the file is `-`.
+fn open_input(cmd: &ArgMatches) -> Result<Box<Read>> {
+    if let Some(text) = cmd.value_of("inputstring") {
+        return Ok(Box::new(io::Cursor::new(text.to_string())));
+    }
+    match cmd.value_of("inputfile") {
+        Some("-") | None => Ok(Box::new(io::stdin())),
+        Some(raw_path) => Ok(Box::new(try!(util::open_path(raw_path)))),
+    }
+}
+
+/// Write to `--output`, or stdout when it is not given or is `-`.
+fn open_output(cmd: &ArgMatches) -> Result<Box<Write>> {
+    match cmd.value_of("output") {
+        Some("-") | None => Ok(Box::new(io::stdout())),
+        Some(raw_path) => Ok(Box::new(try!(util::create_path(raw_path)))),
+    }
+}
+
+/// Parse the value of argument `name`, which the chosen cipher needs.
+fn parse_arg<T>(cmd: &ArgMatches, name: &str) -> Result<T>
+    where T: FromStr,
+          T::Err: error::Error + 'static
+{
+    match cmd.value_of(name) {
+        Some(value) => Ok(try!(value.parse::<T>())),
+        None => Err(From::from(format!("--{} is needed for this cipher", name.replace('_', "-")))),
+    }
+}
+
+/// The ciphers that take their shifts from a keystream, and so can
+/// move their key on with `--key-increment`.
+const INCREMENTING_CIPHERS: [&'static str; 8] = ["vigenere", "beaufort", "variant-beaufort",
+                                                 "porta", "quagmire1", "quagmire2", "quagmire3",
+                                                 "quagmire4"];
+
+/// The key moving on by `--key-increment` every time round, if that was
+/// given. Otherwise the cipher keeps its plain repeating key.
+fn load_incrementing_key(cmd: &ArgMatches,
+                         encoding: &Encoding,
+                         key: &str)
+                         -> Result<Option<Box<Keystream>>> {
+    if !cmd.is_present("key_increment") {
+        return Ok(None);
+    }
+    let key = try!(encoding.vectorize_string(key));
+    let increment = try!(parse_arg(cmd, "key_increment"));
+    Ok(Some(Box::new(try!(cipher::Incrementing::new(key, increment)))))
+}
+
+/// Build the cipher picked by `--cipher`.
+fn load_cipher(cmd: &ArgMatches, encoding: &Encoding, key: Option<&str>) -> Result<Box<Cipher>> {
+    let name = cmd.value_of("cipher").unwrap_or("vigenere");
+    let need_key = || -> Result<&str> {
+        key.ok_or_else(|| From::from(format!("The {} cipher needs a --key or --key-file", name)))
+    };
+    if cmd.is_present("key_increment") && !INCREMENTING_CIPHERS.contains(&name) {
+        return Err(From::from(format!("The {} cipher has no repeating key for \
+                                       --key-increment to move on",
+                                      name)));
+    }
+    match name {
+        "vigenere" => {
+            let key = try!(need_key());
+            match try!(load_incrementing_key(cmd, encoding, key)) {
+                Some(keystream) => Ok(Box::new(cipher::Vigenere::with_keystream(keystream))),
+                None => Ok(Box::new(try!(cipher::Vigenere::from_key(encoding, key)))),
+            }
+        }
+        "autokey" => Ok(Box::new(try!(cipher::Autokey::from_key(encoding, try!(need_key()))))),
+        "beaufort" => {
+            let key = try!(need_key());
+            match try!(load_incrementing_key(cmd, encoding, key)) {
+                Some(keystream) => Ok(Box::new(cipher::Beaufort::with_keystream(keystream))),
+                None => Ok(Box::new(try!(cipher::Beaufort::from_key(encoding, key)))),
+            }
+        }
+        "variant-beaufort" => {
+            let key = try!(need_key());
+            match try!(load_incrementing_key(cmd, encoding, key)) {
+                Some(keystream) => {
+                    Ok(Box::new(cipher::VariantBeaufort::with_keystream(keystream)))
+                }
+                None => Ok(Box::new(try!(cipher::VariantBeaufort::from_key(encoding, key)))),
+            }
+        }
+        "running-key" => {
+            let offset = try!(parse_arg(cmd, "key_offset"));
+            Ok(Box::new(try!(cipher::RunningKey::from_text(encoding, try!(need_key()), offset))))
+        }
+        "affine" => {
+            let a = try!(parse_arg(cmd, "multiplier"));
+            let b = try!(parse_arg(cmd, "shift"));
+            Ok(Box::new(try!(cipher::Affine::new(encoding, a, b))))
+        }
+        "substitution" => {
+            let substitution = match key {
+                Some(key) if cmd.is_present("permutation") => {
+                    try!(cipher::Substitution::from_permutation(encoding, key))
+                }
+                Some(key) => try!(cipher::Substitution::from_keyword(encoding, key)),
+                None => {
+                    match try!(encoding_config(cmd, "encoding")) {
+                        Some(toml) => try!(cipher::Substitution::parse(encoding, &toml)),
+                        None => {
+                            return Err(From::from("The substitution cipher needs a --key, \
+                                                   --key-file, or a [substitution] table in \
+                                                   the --encoding file"))
+                        }
+                    }
+                }
+            };
+            Ok(Box::new(substitution))
+        }
+        "quagmire1" | "quagmire2" | "quagmire3" | "quagmire4" => {
+            let keyword: String = try!(parse_arg(cmd, "alphabet_key"));
+            let indicator = try!(need_key());
+            let quagmire = try!(match name {
+                "quagmire1" => cipher::Quagmire::quagmire1(encoding, &keyword, indicator),
+                "quagmire2" => cipher::Quagmire::quagmire2(encoding, &keyword, indicator),
+                "quagmire3" => cipher::Quagmire::quagmire3(encoding, &keyword, indicator),
+                _ => {
+                    let cipher_keyword: String = try!(parse_arg(cmd, "cipher_alphabet_key"));
+                    cipher::Quagmire::quagmire4(encoding, &keyword, &cipher_keyword, indicator)
+                }
+            });
+            match try!(load_incrementing_key(cmd, encoding, indicator)) {
+                Some(keystream) => Ok(Box::new(quagmire.with_keystream(keystream))),
+                None => Ok(Box::new(quagmire)),
+            }
+        }
+        "playfair" => {
+            let fillers: String = try!(parse_arg(cmd, "filler"));
+            let playfair = try!(cipher::Playfair::new(encoding, try!(need_key()), &fillers));
+            if cmd.is_present("strip_filler") {
+                Ok(Box::new(playfair.stripping_fillers()))
+            } else {
+                Ok(Box::new(playfair))
+            }
+        }
+        "hill" => {
+            let padding = cmd.value_of("padding").unwrap_or("x");
+            match key {
+                Some(key) => Ok(Box::new(try!(cipher::Hill::from_key(encoding, key, padding)))),
+                None => {
+                    match try!(encoding_config(cmd, "encoding")) {
+                        Some(toml) => {
+                            Ok(Box::new(try!(cipher::Hill::parse(encoding, &toml, padding))))
+                        }
+                        None => {
+                            Err(From::from("The hill cipher needs a --key, --key-file, or a \
+                                            [hill] table in the --encoding file"))
+                        }
+                    }
+                }
+            }
+        }
+        "columnar" => {
+            let padding = cmd.value_of("padding").unwrap_or("");
+            Ok(Box::new(try!(cipher::Columnar::new(encoding, try!(need_key()), padding))))
+        }
+        "double-columnar" => {
+            let padding = cmd.value_of("padding").unwrap_or("");
+            let second: String = try!(parse_arg(cmd, "second_key"));
+            Ok(Box::new(try!(cipher::DoubleColumnar::new(encoding,
+                                                         try!(need_key()),
+                                                         &second,
+                                                         padding))))
+        }
+        "rail-fence" => {
+            let rails = try!(parse_arg(cmd, "rails"));
+            let offset = try!(parse_arg(cmd, "rail_offset"));
+            Ok(Box::new(try!(cipher::RailFence::new(rails, offset))))
+        }
+        "scytale" => Ok(Box::new(try!(cipher::Scytale::new(try!(parse_arg(cmd, "diameter")))))),
+        "route" => {
+            let columns = try!(parse_arg(cmd, "columns"));
+            let path = match cmd.value_of("route").unwrap() {
+                "boustrophedon" => cipher::RoutePath::Boustrophedon,
+                _ => cipher::RoutePath::Spiral,
+            };
+            Ok(Box::new(try!(cipher::Route::new(columns, path))))
+        }
+        "polybius" => {
+            let square = try!(cipher::PolybiusSquare::new(encoding, key.unwrap_or("")));
+            let coordinates = match cmd.value_of("coordinates") {
+                Some(symbols) => try!(cipher::Coordinates::new(encoding, symbols, square.side())),
+                None => try!(cipher::Coordinates::first(encoding, square.side())),
+            };
+            Ok(Box::new(cipher::Polybius::new(square, coordinates)))
+        }
+        "bifid" => {
+            let period = try!(parse_arg(cmd, "period"));
+            Ok(Box::new(try!(cipher::Bifid::new(encoding, key.unwrap_or(""), period))))
+        }
+        "trifid" => {
+            let period = try!(parse_arg(cmd, "period"));
+            Ok(Box::new(try!(cipher::Trifid::new(encoding, key.unwrap_or(""), period))))
+        }
+        "adfgx" | "adfgvx" => {
+            let square_keyword = key.unwrap_or("");
+            let transposition: String = try!(parse_arg(cmd, "second_key"));
+            let adfgx = match cmd.value_of("coordinates") {
+                Some(coordinates) => {
+                    cipher::Adfgx::new(encoding, square_keyword, coordinates, &transposition)
+                }
+                None if name == "adfgx" => {
+                    cipher::Adfgx::adfgx(encoding, square_keyword, &transposition)
+                }
+                None => cipher::Adfgx::adfgvx(encoding, square_keyword, &transposition),
+            };
+            Ok(Box::new(try!(adfgx)))
+        }
+        "porta" => {
+            let key = try!(need_key());
+            match try!(load_incrementing_key(cmd, encoding, key)) {
+                Some(keystream) => {
+                    Ok(Box::new(try!(cipher::Porta::with_keystream(encoding, keystream))))
+                }
+                None => Ok(Box::new(try!(cipher::Porta::from_key(encoding, key)))),
+            }
+        }
+        "gronsfeld" => {
+            Ok(Box::new(try!(cipher::Gronsfeld::from_digits(encoding, try!(need_key())))))
+        }
+        "trithemius" => {
+            if key.is_some() {
+                return Err(From::from("The trithemius cipher has no key, so it can not take a \
+                                       --key or --key-file"));
+            }
+            Ok(Box::new(cipher::Vigenere::trithemius()))
+        }
+        "four-square" => {
+            let fillers: String = try!(parse_arg(cmd, "filler"));
+            let second: String = try!(parse_arg(cmd, "second_key"));
+            let four_square =
+                try!(cipher::FourSquare::new(encoding, try!(need_key()), &second, &fillers));
+            if cmd.is_present("strip_filler") {
+                Ok(Box::new(four_square.stripping_fillers()))
+            } else {
+                Ok(Box::new(four_square))
+            }
+        }
+        "two-square" => {
+            let fillers: String = try!(parse_arg(cmd, "filler"));
+            let second: String = try!(parse_arg(cmd, "second_key"));
+            let layout = match cmd.value_of("layout").unwrap() {
+                "horizontal" => cipher::TwoSquareLayout::Horizontal,
+                _ => cipher::TwoSquareLayout::Vertical,
+            };
+            let two_square = try!(cipher::TwoSquare::new(encoding,
+                                                         try!(need_key()),
+                                                         &second,
+                                                         layout,
+                                                         &fillers));
+            if cmd.is_present("strip_filler") {
+                Ok(Box::new(two_square.stripping_fillers()))
+            } else {
+                Ok(Box::new(two_square))
+            }
+        }
+        "checkerboard" | "vic" => {
+            let digits = try!(load_digit_encoding(cmd));
+            let blanks: String = try!(parse_arg(cmd, "blanks"));
+            let checkerboard = try!(cipher::StraddlingCheckerboard::new(encoding,
+                                                                        key.unwrap_or(""),
+                                                                        &digits,
+                                                                        &blanks));
+            if name == "checkerboard" {
+                return Ok(Box::new(checkerboard));
+            }
+            let seed: String = try!(parse_arg(cmd, "chain_seed"));
+            let seed = try!(digits.vectorize_string(&seed));
+            let chain = try!(cipher::ChainAddition::new(seed, digits.size() as u64));
+            Ok(Box::new(cipher::Vic::new(checkerboard, Box::new(chain))))
+        }
+        "nihilist" => {
+            let digits = try!(load_digit_encoding(cmd));
+            let keyword: String = try!(parse_arg(cmd, "second_key"));
+            Ok(Box::new(try!(cipher::Nihilist::new(encoding,
+                                                   key.unwrap_or(""),
+                                                   &keyword,
+                                                   &digits))))
+        }
+        "enigma" => {
+            let enigma = match try!(encoding_config(cmd, "encoding")) {
+                Some(toml) => try!(cipher::Enigma::parse(encoding, &toml)),
+                None => {
+                    return Err(From::from("The enigma cipher needs an [enigma] table in the \
+                                           --encoding file"))
+                }
+            };
+            match key {
+                Some(positions) => Ok(Box::new(try!(enigma.with_positions(encoding, positions)))),
+                None => Ok(Box::new(enigma)),
+            }
+        }
+        // clap only lets through the names in cipher::CIPHERS
+        name => panic!("Attempted to transcode with unknown cipher '{}'!", name),
+    }
+}
+
 fn transcode(action: Action, cmd: &ArgMatches) -> Result<()> {
     debug!("Running {:?} -> {:?}\n", action, cmd);
-    let encoding = shifty::alphanumeric_space();
+    let encoding = try!(load_encoding(cmd));
 
 
-    let mut key = if cmd.is_present("keystring") {
-        cmd.value_of("keystring").unwrap().to_string()
-    } else if cmd.is_present("keyfile") {
-        let raw_path = cmd.value_of("keyfile").unwrap();
-        try!(util::read_path(raw_path))
+    let keytext = if let Some(text) = cmd.value_of("keystring") {
+        Some(text.to_string())
+    } else if let Some(raw_path) = cmd.value_of("keyfile") {
+        Some(try!(util::read_path(raw_path)))
     } else {
-        // clap should force the user to pick one or the other
-        panic!("Attempted to transcode without either keystring or keyfile set!")
+        None
     };
 
-    let mut input = if cmd.is_present("inputstring") {
-        cmd.value_of("inputstring").unwrap().to_string()
-    } else if cmd.is_present("inputfile") {
-        let raw_path = cmd.value_of("inputfile").unwrap();
-        try!(util::read_path(raw_path))
+    let unknown = if cmd.is_present("report_unknown") {
+        UnknownChars::Report
+    } else if cmd.is_present("passthrough") {
+        UnknownChars::Passthrough { advance_key: cmd.is_present("advance_key") }
+    } else if cmd.is_present("strict") {
+        UnknownChars::Fail
     } else {
-        // clap should force the user to pick one or the other
-        panic!("Attempted to transcode without either inputstring or inputfile set!")
+        UnknownChars::Drop
     };
 
+    // A running key is a passage of ordinary text, so it is always normalized.
+    let key_unknown = match cmd.value_of("cipher") {
+        Some("running-key") => UnknownChars::Drop,
+        _ => unknown,
+    };
+    let key = match keytext {
+        // Gronsfeld keys are the shifts written as digits, not characters of the encoding.
+        Some(keytext) if cmd.value_of("cipher") == Some("gronsfeld") => Some(keytext),
+        Some(keytext) => {
+            let check = try!(encoding.check_key(&keytext, key_unknown));
+            if !check.dropped.is_empty() {
+                try!(writeln!(io::stderr(),
+                              "Dropped characters from the key that are not in the encoding: {:?}",
+                              check.dropped));
+            }
+            info!("Key: {}", check.key);
+            Some(check.key)
+        }
+        None => None,
+    };
+    let cipher = try!(load_cipher(cmd, &encoding, key.as_ref().map(|k| &k[..])));
+    let mut input = util::Chomp::new(try!(open_input(cmd)));
+    let mut output = try!(open_output(cmd));
+    let name = cmd.value_of("cipher").unwrap_or("vigenere");
+    if cipher::NUMERIC_CIPHERS.contains(&name) {
+        // Letters go in and digits come out, or the other way round.
+        let digits = try!(load_digit_encoding(cmd));
+        let (from, to) = match action {
+            Action::Encrypt => (&encoding, &digits),
+            Action::Decrypt => (&digits, &encoding),
+        };
+        try!(from.transform_stream_into(to, &mut input, &mut output, &*cipher, action, unknown));
+    } else {
+        try!(encoding.transform_stream(&mut input, &mut output, &*cipher, action, unknown));
+    }
+
+    // Give back the line ending we held off the input, so that
+    // `encrypt | decrypt` reproduces its input byte for byte.
+    try!(output.write_all(input.line_ending()));
+    if cmd.is_present("inputstring") && !cmd.is_present("output") {
+        try!(writeln!(output, ""));
+    }
+    Ok(())
+}
+
+/// The n-gram model trained on `--corpus`, or on the built-in sample.
+fn load_model(cmd: &ArgMatches, encoding: &Encoding) -> Result<analysis::NgramModel> {
+    match cmd.value_of("corpus") {
+        Some(path) => {
+            let mut corpus = String::new();
+            try!(try!(util::open_path(path)).read_to_string(&mut corpus));
+            Ok(try!(analysis::NgramModel::train(encoding, &corpus, 3)))
+        }
+        None => Ok(try!(analysis::NgramModel::english(encoding))),
+    }
+}
+
+fn crack(cmd: &ArgMatches) -> Result<()> {
+    debug!("Running crack -> {:?}\n", cmd);
+    let encoding = try!(load_encoding(cmd));
+
+    let mut input = String::new();
+    try!(try!(open_input(cmd)).read_to_string(&mut input));
+    input = input.trim().to_string();
 
     if !cmd.is_present("strict") {
-        key = encoding.map_filter_string(&key);
         input = encoding.map_filter_string(&input);
     }
 
-    info!("Input: {}", input);
-    info!("Key: {}", key);
-    let output = try!(encoding.transform_message(&input, &key, action));
-    println!("{}", output);
+    let count = try!(cmd.value_of("candidates").unwrap().parse::<usize>());
+    let language = analysis::Language::english();
+
+    let candidates = match cmd.value_of("cipher").unwrap() {
+        "substitution" => {
+            let model = try!(load_model(cmd, &encoding));
+            let options = analysis::AnnealOptions {
+                restarts: try!(parse_arg(cmd, "restarts")),
+                iterations: try!(parse_arg(cmd, "iterations")),
+                ..analysis::AnnealOptions::default()
+            };
+            vec![try!(analysis::crack_substitution(&encoding, &input, &language, &model, &options))]
+        }
+        "rail-fence" | "scytale" | "route" | "transposition" => {
+            let model = try!(load_model(cmd, &encoding));
+            try!(match cmd.value_of("cipher").unwrap() {
+                "rail-fence" => analysis::crack_rail_fence(&encoding, &input, &model),
+                "scytale" => analysis::crack_scytale(&encoding, &input, &model),
+                "route" => analysis::crack_route(&encoding, &input, &model),
+                _ => analysis::crack_transposition(&encoding, &input, &model),
+            })
+        }
+        "hill" => {
+            let mut crib: String = try!(parse_arg(cmd, "known_plaintext"));
+            if !cmd.is_present("strict") {
+                crib = encoding.map_filter_string(&crib);
+            }
+            let block = try!(parse_arg(cmd, "block_size"));
+            let key = try!(analysis::recover_hill_key(&encoding, &crib, &input, block));
+            let hill = try!(cipher::Hill::new(&encoding, key, ""));
+            let length = input.chars().count() / block * block;
+            let whole_blocks: String = input.chars().take(length).collect();
+            println!("matrix = {:?}", hill.key());
+            println!("{}", try!(encoding.transform_with(&whole_blocks, &hill, Action::Decrypt)));
+            return Ok(());
+        }
+        _ => {
+            let max_key_length = try!(parse_arg(cmd, "max_key_length"));
+            try!(analysis::crack_vigenere(&encoding, &input, &language, max_key_length))
+        }
+    };
+    if candidates.is_empty() {
+        return Err(From::from("No candidates: the ciphertext is too short to try any keys"));
+    }
+    for (rank, candidate) in candidates.iter().take(count).enumerate() {
+        println!("{}. key {:?} (score {:.3})",
+                 rank + 1,
+                 candidate.key,
+                 candidate.score);
+        println!("{}", candidate.plaintext);
+    }
     Ok(())
 }
 
+/// Arguments shared by `encrypt` and `decrypt`.
+fn transcode_command(name: &'static str) -> App<'static, 'static> {
+    SubCommand::with_name(name)
+        .arg(Arg::with_name("keystring")
+            .short("k")
+            .long("key")
+            .value_name("KEYSTRING")
+            .takes_value(true)
+            .help("The secret key"))
+        .arg(Arg::with_name("keyfile")
+            .long("key-file")
+            .value_name("KEYFILE")
+            .takes_value(true)
+            .help("Path to file with secret key"))
+        .arg(Arg::with_name("key_offset")
+            .long("key-offset")
+            .value_name("OFFSET")
+            .takes_value(true)
+            .default_value("0")
+            .help("Characters of the key to skip before starting (running-key)"))
+        .arg(Arg::with_name("key_increment")
+            .long("key-increment")
+            .value_name("N")
+            .takes_value(true)
+            .help("Shift the whole key on by N every time it repeats \
+                   (vigenere, beaufort, variant-beaufort, porta, quagmire1-4)"))
+        .arg(Arg::with_name("permutation")
+            .long("permutation")
+            .help("The key is the whole alphabet reordered, not a keyword (substitution)"))
+        .arg(Arg::with_name("multiplier")
+            .long("multiplier")
+            .value_name("A")
+            .takes_value(true)
+            .help("Multiplier coprime to the alphabet size (affine)"))
+        .arg(Arg::with_name("shift")
+            .long("shift")
+            .value_name("B")
+            .takes_value(true)
+            .help("Amount added after multiplying (affine)"))
+        .arg(Arg::with_name("alphabet_key")
+            .long("alphabet-key")
+            .value_name("KEYWORD")
+            .takes_value(true)
+            .help("Keyword that mixes the alphabet, or the plaintext alphabet of quagmire4 \
+                   (quagmire1-4)"))
+        .arg(Arg::with_name("cipher_alphabet_key")
+            .long("cipher-alphabet-key")
+            .value_name("KEYWORD")
+            .takes_value(true)
+            .help("Keyword that mixes the ciphertext alphabet (quagmire4)"))
+        .arg(Arg::with_name("filler")
+            .long("filler")
+            .value_name("CHARS")
+            .takes_value(true)
+            .default_value("xq")
+            .help("Characters that split doubled letters and pad odd messages, in order of \
+                   preference (playfair, four-square, two-square)"))
+        .arg(Arg::with_name("padding")
+            .long("padding")
+            .value_name("CHARS")
+            .takes_value(true)
+            .help("Characters to fill out the last block or row with, used in turn (hill, \
+                   which defaults to x, and columnar, which defaults to no padding)"))
+        .arg(Arg::with_name("rails")
+            .long("rails")
+            .value_name("COUNT")
+            .takes_value(true)
+            .help("Number of rails (rail-fence)"))
+        .arg(Arg::with_name("rail_offset")
+            .long("rail-offset")
+            .value_name("OFFSET")
+            .takes_value(true)
+            .default_value("0")
+            .help("Steps into the zigzag the first character starts (rail-fence)"))
+        .arg(Arg::with_name("diameter")
+            .long("diameter")
+            .value_name("COUNT")
+            .takes_value(true)
+            .help("Characters around the rod (scytale)"))
+        .arg(Arg::with_name("columns")
+            .long("columns")
+            .value_name("COUNT")
+            .takes_value(true)
+            .help("Width of the grid (route)"))
+        .arg(Arg::with_name("route")
+            .long("route")
+            .value_name("PATH")
+            .takes_value(true)
+            .possible_values(&["spiral", "boustrophedon"])
+            .default_value("spiral")
+            .help("Path to read the grid along (route)"))
+        .arg(Arg::with_name("second_key")
+            .long("second-key")
+            .value_name("KEYWORD")
+            .takes_value(true)
+            .help("Keyword for the second transposition (double-columnar), for the \
+                   transposition (adfgx, adfgvx), for the second square (four-square, \
+                   two-square), or to add to the message (nihilist)"))
+        .arg(Arg::with_name("layout")
+            .long("layout")
+            .value_name("LAYOUT")
+            .takes_value(true)
+            .possible_values(&["vertical", "horizontal"])
+            .default_value("vertical")
+            .help("Whether the second square is below or beside the first (two-square)"))
+        .arg(Arg::with_name("digit_encoding")
+            .long("digit-encoding")
+            .value_name("ENCODING")
+            .takes_value(true)
+            .default_value("digits")
+            .help("Encoding the ciphertext digits are written with, a built-in name or \
+                   a TOML file (checkerboard, nihilist, vic)"))
+        .arg(Arg::with_name("blanks")
+            .long("blanks")
+            .value_name("DIGITS")
+            .takes_value(true)
+            .help("Digits left blank in the top row, each starting a row of its own \
+                   (checkerboard, vic)"))
+        .arg(Arg::with_name("chain_seed")
+            .long("chain-seed")
+            .value_name("DIGITS")
+            .takes_value(true)
+            .help("Digits that start the chain-addition keystream (vic)"))
+        .arg(Arg::with_name("coordinates")
+            .long("coordinates")
+            .value_name("CHARS")
+            .takes_value(true)
+            .help("Characters naming the rows and columns of the square (polybius, which \
+                   defaults to the start of the alphabet, and adfgx, adfgvx)"))
+        .arg(Arg::with_name("period")
+            .long("period")
+            .value_name("LENGTH")
+            .takes_value(true)
+            .default_value("0")
+            .help("Characters fractionated together, or 0 for the whole message (bifid, trifid)"))
+        .arg(Arg::with_name("strip_filler")
+            .long("strip-filler")
+            .help("Take the filler characters back out when decrypting (playfair, \
+                   four-square, two-square)"))
+        .group(ArgGroup::with_name("key_source")
+            .arg("keystring")
+            .arg("keyfile"))
+        .arg(Arg::with_name("inputstring")
+            .short("i")
+            .long("input")
+            .takes_value(true)
+            .help("Text to be transformed"))
+        .arg(Arg::with_name("inputfile")
+            .long("input-file")
+            .takes_value(true)
+            .help("Path to file with text to be transformed, or - for stdin"))
+        .group(ArgGroup::with_name("input_source")
+            .arg("inputstring")
+            .arg("inputfile"))
+        .arg(Arg::with_name("output")
+            .short("o")
+            .long("output")
+            .value_name("OUTPUT")
+            .takes_value(true)
+            .help("Path to write the transformed text to, or - for stdout"))
+        .arg(Arg::with_name("encoding")
+            .short("e")
+            .long("encoding")
+            .value_name("ENCODING")
+            .takes_value(true)
+            .help("Name of a built-in encoding, or path to a TOML encoding file"))
+        .arg(Arg::with_name("cipher")
+            .short("c")
+            .long("cipher")
+            .value_name("CIPHER")
+            .takes_value(true)
+            .possible_values(&cipher::CIPHERS)
+            .default_value("vigenere")
+            .help("The algorithm to encrypt or decrypt with"))
+        .arg(Arg::with_name("strict")
+            .long("strict")
+            .help("Fail if unknown characters are encountered"))
+        .arg(Arg::with_name("report_unknown")
+            .long("report-unknown")
+            .help("Like --strict, but list every unknown character before failing"))
+        .arg(Arg::with_name("passthrough")
+            .long("passthrough")
+            .help("Copy unknown characters to the output unchanged, and keep letter case"))
+        .arg(Arg::with_name("advance_key")
+            .long("advance-key")
+            .requires("passthrough")
+            .help("With --passthrough, let copied characters use up a key position (only \
+                   ciphers that work one character at a time, other than autokey)"))
+}
+
 fn main() {
     env_logger::init().unwrap();
     let cli_context = App::new("caesar")
         .version("0.1.0")
         .author("Scott Schroeder <scottschroeder@sent.com>")
-        .about("A CLI tool for working with the Vigenère cipher.")
+        .about("A CLI tool for working with classical ciphers.")
         .arg(Arg::with_name("v")
             .short("v")
             .multiple(true)
             .help("Sets the level of verbosity"))
-        .subcommand(SubCommand::with_name("encrypt")
-            .arg(Arg::with_name("keystring")
-                .short("k")
-                .long("key")
-                .value_name("KEYSTRING")
-                .takes_value(true)
-                .help("The secret key"))
-            .arg(Arg::with_name("keyfile")
-                .long("key-file")
-                .value_name("KEYFILE")
-                .takes_value(true)
-                .help("Path to file with secret key"))
-            .group(ArgGroup::with_name("key_source")
-                .arg("keystring")
-                .arg("keyfile")
-                .required(true))
+        .subcommand(transcode_command("encrypt").about("Encrypt plaintext"))
+        .subcommand(transcode_command("decrypt").about("Decrypt ciphertext"))
+        .subcommand(SubCommand::with_name("crack")
+            .about("Recover the key from Vigenère, substitution, Hill or transposition ciphertext")
             .arg(Arg::with_name("inputstring")
                 .short("i")
                 .long("input")
                 .takes_value(true)
-                .help("Text to be encrypted"))
+                .help("Text to be cracked"))
             .arg(Arg::with_name("inputfile")
                 .long("input-file")
                 .takes_value(true)
-                .help("Path to file with text to be encrypted"))
-            .arg(Arg::with_name("strict")
-                .long("strict")
-                .help("Fail if unknown characters are encountered"))
+                .help("Path to file with text to be cracked, or - for stdin"))
             .group(ArgGroup::with_name("input_source")
                 .arg("inputstring")
-                .arg("inputfile")
-                .required(true)))
-        .subcommand(SubCommand::with_name("decrypt")
-            .arg(Arg::with_name("keystring")
-                .short("k")
-                .long("key")
-                .value_name("KEYSTRING")
+                .arg("inputfile"))
+            .arg(Arg::with_name("encoding")
+                .short("e")
+                .long("encoding")
+                .value_name("ENCODING")
                 .takes_value(true)
-                .help("The secret key"))
-            .arg(Arg::with_name("keyfile")
-                .long("key-file")
-                .value_name("KEYFILE")
+                .help("Name of a built-in encoding, or path to a TOML encoding file"))
+            .arg(Arg::with_name("cipher")
+                .short("c")
+                .long("cipher")
+                .value_name("CIPHER")
                 .takes_value(true)
-                .help("Path to file with secret key"))
-            .group(ArgGroup::with_name("key_source")
-                .arg("keystring")
-                .arg("keyfile")
-                .required(true))
-            .arg(Arg::with_name("inputstring")
-                .short("i")
-                .long("input")
+                .possible_values(&["vigenere",
+                                   "substitution",
+                                   "hill",
+                                   "rail-fence",
+                                   "scytale",
+                                   "route",
+                                   "transposition"])
+                .default_value("vigenere")
+                .help("Cipher the text was encrypted with"))
+            .arg(Arg::with_name("max_key_length")
+                .long("max-key-length")
+                .value_name("LENGTH")
                 .takes_value(true)
-                .help("Text to be decrypted"))
-            .arg(Arg::with_name("inputfile")
-                .long("input-file")
+                .default_value("20")
+                .help("Longest key to consider"))
+            .arg(Arg::with_name("restarts")
+                .long("restarts")
+                .value_name("COUNT")
                 .takes_value(true)
-                .help("Path to file with text to be decrypted"))
-            .group(ArgGroup::with_name("input_source")
-                .arg("inputstring")
-                .arg("inputfile")
-                .required(true)))
+                .default_value("8")
+                .help("Number of fresh starts for the substitution search"))
+            .arg(Arg::with_name("iterations")
+                .long("iterations")
+                .value_name("COUNT")
+                .takes_value(true)
+                .default_value("20000")
+                .help("Key swaps to try on each substitution search"))
+            .arg(Arg::with_name("corpus")
+                .long("corpus")
+                .value_name("FILE")
+                .takes_value(true)
+                .help("Text to train the n-gram scorer on instead of the built-in sample \
+                       (substitution and transpositions)"))
+            .arg(Arg::with_name("known_plaintext")
+                .long("known-plaintext")
+                .value_name("TEXT")
+                .takes_value(true)
+                .help("Plaintext of the start of the message (hill)"))
+            .arg(Arg::with_name("block_size")
+                .long("block-size")
+                .value_name("SIZE")
+                .takes_value(true)
+                .help("Number of rows in the key matrix (hill)"))
+            .arg(Arg::with_name("candidates")
+                .short("n")
+                .long("candidates")
+                .value_name("COUNT")
+                .takes_value(true)
+                .default_value("3")
+                .help("Number of candidate keys to print"))
+            .arg(Arg::with_name("strict")
+                .long("strict")
+                .help("Fail if unknown characters are encountered")))
         .arg(Arg::with_name("strict")
             .long("strict")
             .help("Fail if unknown characters are encountered"))
@@ -146,6 +803,7 @@ fn main() {
     let result = match cli_context.subcommand() {
         ("encrypt", Some(cmd)) => transcode(Action::Encrypt, cmd),
         ("decrypt", Some(cmd)) => transcode(Action::Decrypt, cmd),
+        ("crack", Some(cmd)) => crack(cmd),
         (unkown_cmd, Some(_)) => panic!("Unknown command '{}'", unkown_cmd),
         _ => {
             println!("{}", cli_context.usage());
@@ -156,6 +814,9 @@ fn main() {
 
     match result {
         Ok(_) => (),
-        Err(e) => println!("Encountered an error: {}", e),
+        Err(e) => {
+            writeln!(io::stderr(), "Encountered an error: {}", e).unwrap();
+            std::process::exit(1);
+        }
     }
 }
@@ -0,0 +1,104 @@
+//! Recovering a Hill cipher key from known plaintext.
+//!
+//! Every block of plaintext `p` and its ciphertext `c` give the equations
+//! `K p = c`, which are linear in the entries of the key `K`. With enough
+//! blocks whose plaintext spans the whole space, solving them modulo the
+//! alphabet size pins down `K` exactly.
+
+use super::super::{Encoding, Error, Result};
+use super::super::modular::{self, Matrix};
+
+/// Find the `block` by `block` key matrix that turns `plaintext` into
+/// `ciphertext`. The two only need to line up as far as the shorter one
+/// goes, so a crib from the start of a longer message is enough.
+///
+/// Fails with `Error::SingularMatrix` when the plaintext blocks do not
+/// pin down a single key, usually because there are too few of them.
+pub fn recover_hill_key(encoding: &Encoding,
+                        plaintext: &str,
+                        ciphertext: &str,
+                        block: usize)
+                        -> Result<Matrix> {
+    if block == 0 {
+        return Err(Error::ZeroParameter("symbols in a block"));
+    }
+    let size = encoding.size();
+    if size == 0 {
+        return Err(Error::EmptyEncoding);
+    }
+    let modulus = size as u64;
+    let plain = try!(encoding.vectorize_string(plaintext));
+    let cipher = try!(encoding.vectorize_string(ciphertext));
+
+    // Each block is a row of the system P^T K^T = C^T, written next to its
+    // right-hand side.
+    let mut rows: Vec<Vec<u64>> = plain.chunks(block)
+        .zip(cipher.chunks(block))
+        .filter(|&(p, c)| p.len() == block && c.len() == block)
+        .map(|(p, c)| p.iter().chain(c).map(|n| u64::from(*n)).collect())
+        .collect();
+    while rows.len() < block {
+        rows.push(vec![0; 2 * block]);
+    }
+    debug!("Solving for a Hill key with {} blocks", rows.len());
+
+    modular::triangulate(&mut rows, block, modulus);
+    if rows[block..].iter().any(|row| row.iter().any(|x| *x != 0)) {
+        warn!("Some blocks do not agree with the rest about the key");
+    }
+    rows.truncate(block);
+    if modular::back_substitute(&mut rows, block, modulus).is_none() {
+        let square: Matrix = rows.iter().map(|row| row[..block].to_vec()).collect();
+        return Err(Error::SingularMatrix(modular::determinant(&square, modulus), size));
+    }
+
+    // The right-hand side now holds K^T.
+    Ok((0..block).map(|r| (0..block).map(|c| rows[c][block + r]).collect()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::{alpha, Error};
+    use super::super::super::cipher::{Cipher, Hill};
+
+    fn encrypt(e: &Encoding, h: &Hill, text: &str) -> String {
+        let m = e.vectorize_string(text).unwrap();
+        h.encrypt(e, &m).unwrap().iter().map(|n| e.number_to_char(n).unwrap()).collect()
+    }
+
+    #[test]
+    fn recover_two_by_two() {
+        let e = alpha();
+        assert_eq!(recover_hill_key(&e, "helpme", "hiatws", 2).unwrap(),
+                   vec![vec![3, 3], vec![2, 5]]);
+    }
+
+    #[test]
+    fn recover_from_crib() {
+        let e = alpha();
+        let h = Hill::from_key(&e, "gybnqkurp", "x").unwrap();
+        let ciphertext = encrypt(&e, &h, "attackthenorthgateatdawnwithallthecavalry");
+        let key = recover_hill_key(&e, "attackthenorthgate", &ciphertext, 3).unwrap();
+        assert_eq!(&key, h.key());
+    }
+
+    #[test]
+    fn too_little_plaintext() {
+        let e = alpha();
+        match recover_hill_key(&e, "help", "hiat", 3) {
+            Err(Error::SingularMatrix(0, 26)) => (),
+            Ok(_) => panic!("We found a key from a single block!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn reject_zero_block_size() {
+        match recover_hill_key(&alpha(), "abcd", "abcd", 0) {
+            Err(Error::ZeroParameter(_)) => (),
+            Ok(_) => panic!("We recovered a key with no rows!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Frequency analysis for breaking ciphertext.
+//!
+//! Everything in here works on the numbers an `Encoding` assigns to its
+//! characters, so any alphabet can be attacked as long as we know roughly
+//! how often each of its characters shows up in the language.
+
+use std::collections::HashMap;
+use super::Encoding;
+use super::EncodeNum;
+use super::Result;
+
+pub mod vigenere;
+mod ngram;
+mod substitution;
+mod hill;
+mod transposition;
+
+pub use self::vigenere::crack_vigenere;
+pub use self::ngram::NgramModel;
+pub use self::substitution::{crack_substitution, AnnealOptions};
+pub use self::hill::recover_hill_key;
+pub use self::transposition::{crack_rail_fence, crack_route, crack_scytale,
+                              crack_transposition};
+
+/// Relative frequencies of English text, letters sum to 100.
+/// Space is weighted so that it makes up about 18% of running text.
+const ENGLISH: [(char, f64); 27] = [('a', 8.167),
+                                    ('b', 1.492),
+                                    ('c', 2.782),
+                                    ('d', 4.253),
+                                    ('e', 12.702),
+                                    ('f', 2.228),
+                                    ('g', 2.015),
+                                    ('h', 6.094),
+                                    ('i', 6.966),
+                                    ('j', 0.153),
+                                    ('k', 0.772),
+                                    ('l', 4.025),
+                                    ('m', 2.406),
+                                    ('n', 6.749),
+                                    ('o', 7.507),
+                                    ('p', 1.929),
+                                    ('q', 0.095),
+                                    ('r', 5.987),
+                                    ('s', 6.327),
+                                    ('t', 9.056),
+                                    ('u', 2.758),
+                                    ('v', 0.978),
+                                    ('w', 2.360),
+                                    ('x', 0.150),
+                                    ('y', 1.974),
+                                    ('z', 0.074),
+                                    (' ', 21.5)];
+
+/// Weight given to characters the language table knows nothing about,
+/// such as digits. Keeps every expected count above zero.
+const UNKNOWN_WEIGHT: f64 = 0.05;
+
+/// Character frequencies of a natural language.
+#[derive(Debug)]
+pub struct Language {
+    weights: HashMap<char, f64>,
+}
+
+impl Language {
+    pub fn english() -> Language {
+        Language { weights: ENGLISH.iter().cloned().collect() }
+    }
+
+    fn weight(&self, c: char) -> f64 {
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        *self.weights.get(&lower).unwrap_or(&UNKNOWN_WEIGHT)
+    }
+
+    /// Probability of each symbol of `encoding`, indexed by its `EncodeNum`.
+    pub fn expected(&self, encoding: &Encoding) -> Result<Vec<f64>> {
+        let mut weights = Vec::with_capacity(encoding.size());
+        for i in 0..encoding.size() {
+            let c = try!(encoding.number_to_char(&EncodeNum::from(i as u64)));
+            weights.push(self.weight(c));
+        }
+        let total: f64 = weights.iter().sum();
+        Ok(weights.iter().map(|w| w / total).collect())
+    }
+}
+
+/// A possible key for the ciphertext, along with what it decrypts to.
+#[derive(Debug)]
+pub struct Candidate {
+    pub key: String,
+    pub plaintext: String,
+    pub score: f64,
+}
+
+fn to_indices(text: &[EncodeNum]) -> Vec<usize> {
+    text.iter().map(|n| u64::from(*n) as usize).collect()
+}
+
+fn counts(text: &[usize], size: usize) -> Vec<usize> {
+    let mut counts = vec![0; size];
+    for n in text {
+        counts[*n] += 1;
+    }
+    counts
+}
+
+/// Small xorshift generator, so searches can be repeated from a seed.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        // Zero would get stuck at zero forever.
+        Rng(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Uniform in `0..bound`, which must not be empty.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
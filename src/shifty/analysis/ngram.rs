@@ -0,0 +1,155 @@
+//! Scoring how much a piece of text looks like the language,
+//! by how likely each run of `order` symbols is.
+
+use super::super::{Encoding, Error, Result};
+use super::to_indices;
+
+/// Sample of English prose to learn n-gram statistics from.
+const ENGLISH_SAMPLE: &'static str = include_str!("english.txt");
+
+/// Largest table we are willing to build, in entries.
+const MAX_TABLE_SIZE: usize = 1 << 22;
+
+/// Log-probabilities of every n-gram over an encoding's alphabet.
+///
+/// Training text is rarely big enough to see every n-gram, so each
+/// probability mixes in the shorter n-grams ending the same way,
+/// giving longer ones more weight.
+#[derive(Debug)]
+pub struct NgramModel {
+    order: usize,
+    size: usize,
+    log_probabilities: Vec<f64>,
+}
+
+/// Number of times each k-gram shows up in `text`, indexed so
+/// the last symbol of the k-gram varies fastest.
+fn count_kgrams(text: &[usize], size: usize, k: usize) -> Vec<f64> {
+    let mut counts = vec![0.0; size.pow(k as u32)];
+    if k > 0 && text.len() >= k {
+        for window in text.windows(k) {
+            counts[window.iter().fold(0, |index, n| index * size + n)] += 1.0;
+        }
+    }
+    counts
+}
+
+impl NgramModel {
+    /// Learn n-grams of length `order` from `text`, after normalizing it
+    /// with the encoding's mapping the same way messages are.
+    pub fn train(encoding: &Encoding, text: &str, order: usize) -> Result<NgramModel> {
+        let size = encoding.size();
+        if size == 0 {
+            return Err(Error::EmptyEncoding);
+        }
+        let order = ::std::cmp::max(1, order);
+        let mut order_ok = order;
+        while order_ok > 1 && size.pow(order_ok as u32) > MAX_TABLE_SIZE {
+            order_ok -= 1;
+        }
+        if order_ok != order {
+            warn!("Alphabet of {} is too big for {}-grams, using {}-grams",
+                  size,
+                  order,
+                  order_ok);
+        }
+        let order = order_ok;
+
+        let sample = try!(encoding.vectorize_string(&encoding.map_filter_string(text)));
+        let sample = to_indices(&sample);
+        let counts: Vec<Vec<f64>> =
+            (0..order + 1).map(|k| count_kgrams(&sample, size, k)).collect();
+        // How often each context was followed by anything at all.
+        let followed: Vec<Vec<f64>> = counts.iter()
+            .map(|c| c.chunks(size).map(|next| next.iter().sum()).collect())
+            .collect();
+
+        let weights: Vec<f64> = (1..order + 1).map(|k| (1 << k) as f64).collect();
+        let total_weight: f64 = weights.iter().sum();
+
+        let table_size = size.pow(order as u32);
+        let mut log_probabilities = Vec::with_capacity(table_size);
+        for index in 0..table_size {
+            // Add one, so symbols never seen still have a chance.
+            let unigram = (counts[1][index % size] + 1.0) / (sample.len() + size) as f64;
+            let mut probability = weights[0] / total_weight * unigram;
+            for k in 2..order + 1 {
+                // The k-gram is the last k symbols, its context the first k - 1.
+                let kgram = index % size.pow(k as u32);
+                let context = kgram / size;
+                let estimate = if followed[k][context] > 0.0 {
+                    counts[k][kgram] / followed[k][context]
+                } else {
+                    unigram
+                };
+                probability += weights[k - 1] / total_weight * estimate;
+            }
+            log_probabilities.push(probability.ln());
+        }
+
+        Ok(NgramModel {
+            order: order,
+            size: size,
+            log_probabilities: log_probabilities,
+        })
+    }
+
+    /// Trigrams learned from a sample of English prose.
+    pub fn english(encoding: &Encoding) -> Result<NgramModel> {
+        NgramModel::train(encoding, ENGLISH_SAMPLE, 3)
+    }
+
+    /// Sum of the log-probabilities of every n-gram in `text`,
+    /// given as the indices of its symbols. Higher is better.
+    pub fn score(&self, text: &[usize]) -> f64 {
+        if text.len() < self.order {
+            return 0.0;
+        }
+        let top = self.size.pow(self.order as u32 - 1);
+        let mut index = text[..self.order - 1].iter().fold(0, |index, n| index * self.size + n);
+        let mut score = 0.0;
+        for n in &text[self.order - 1..] {
+            index = (index % top) * self.size + n;
+            score += self.log_probabilities[index];
+        }
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::{alpha, Encoding, Error};
+
+    fn score(model: &NgramModel, text: &str) -> f64 {
+        let e = alpha();
+        let numbers = e.vectorize_string(text).unwrap();
+        model.score(&super::super::to_indices(&numbers))
+    }
+
+    #[test]
+    fn english_beats_gibberish() {
+        let model = NgramModel::english(&alpha()).unwrap();
+        assert!(score(&model, "thequickbrownfox") > score(&model, "qzxjvkwqpzxjqvkw"));
+        assert!(score(&model, "thequickbrownfox") > score(&model, "xofnworbkciuqeht"));
+    }
+
+    #[test]
+    fn probabilities_sum_to_one() {
+        let model = NgramModel::train(&alpha(), "abcabcabd", 2).unwrap();
+        for context in 0..26 {
+            let total: f64 =
+                (0..26).map(|n| model.log_probabilities[context * 26 + n].exp()).sum();
+            assert!((total - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn reject_empty_encoding() {
+        match NgramModel::train(&Encoding::new(), "abc", 3) {
+            Err(Error::EmptyEncoding) => (),
+            Ok(_) => panic!("We trained a model with no alphabet!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
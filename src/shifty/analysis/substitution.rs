@@ -0,0 +1,220 @@
+//! Breaking simple substitution by simulated annealing.
+//!
+//! Starting from the key which lines up symbol frequencies with the
+//! language, we keep swapping pairs of letters in the key and keep the
+//! swaps that make the plaintext score better under an n-gram model.
+//! Swaps that make it worse are sometimes kept too, less and less often
+//! as the search cools, so that it can climb out of local maxima.
+
+use std::cmp::Ordering;
+use super::{counts, to_indices, Candidate, Language, NgramModel, Rng};
+use super::super::{EncodeNum, Encoding, Error, Result};
+
+/// How hard to search for a substitution key.
+#[derive(Debug, Clone, Copy)]
+pub struct AnnealOptions {
+    /// Number of searches, each starting from a shuffled key
+    pub restarts: usize,
+    /// Swaps to try in each search
+    pub iterations: usize,
+    /// Starting temperature, which cools linearly to zero
+    pub temperature: f64,
+    /// Seed for the random swaps, so runs can be repeated
+    pub seed: u64,
+}
+
+impl Default for AnnealOptions {
+    fn default() -> AnnealOptions {
+        AnnealOptions {
+            restarts: 8,
+            iterations: 20000,
+            temperature: 10.0,
+            seed: 0x5eed,
+        }
+    }
+}
+
+/// Decryption key which maps the most common ciphertext symbol to the
+/// most likely plaintext symbol, the second to the second, and so on.
+fn frequency_key(text: &[usize], expected: &[f64]) -> Vec<usize> {
+    let size = expected.len();
+    let observed = counts(text, size);
+    let mut by_count: Vec<usize> = (0..size).collect();
+    by_count.sort_by(|a, b| observed[*b].cmp(&observed[*a]));
+    let mut by_expected: Vec<usize> = (0..size).collect();
+    by_expected.sort_by(|a, b| {
+        expected[*b].partial_cmp(&expected[*a]).unwrap_or(Ordering::Equal)
+    });
+
+    let mut key = vec![0; size];
+    for (c, p) in by_count.iter().zip(by_expected.iter()) {
+        key[*c] = *p;
+    }
+    key
+}
+
+fn decrypt_with(key: &[usize], text: &[usize], plaintext: &mut Vec<usize>) {
+    plaintext.clear();
+    plaintext.extend(text.iter().map(|c| key[*c]));
+}
+
+/// Find the substitution key that makes `ciphertext` read most like the
+/// language `model` was trained on.
+///
+/// The key in the result is written the way `Substitution::from_permutation`
+/// takes it: the ciphertext character for each plaintext character in turn.
+pub fn crack_substitution(encoding: &Encoding,
+                          ciphertext: &str,
+                          language: &Language,
+                          model: &NgramModel,
+                          options: &AnnealOptions)
+                          -> Result<Candidate> {
+    let size = encoding.size();
+    if size == 0 {
+        return Err(Error::EmptyEncoding);
+    }
+    let text = to_indices(&try!(encoding.vectorize_string(ciphertext)));
+    let expected = try!(language.expected(encoding));
+    let start = frequency_key(&text, &expected);
+    let mut rng = Rng::new(options.seed);
+    let mut plaintext = Vec::with_capacity(text.len());
+
+    let mut best_key = start.clone();
+    decrypt_with(&best_key, &text, &mut plaintext);
+    let mut best_score = model.score(&plaintext);
+
+    for restart in 0..options.restarts {
+        let mut key = start.clone();
+        if restart > 0 {
+            for _ in 0..size {
+                key.swap(rng.below(size), rng.below(size));
+            }
+        }
+        decrypt_with(&key, &text, &mut plaintext);
+        let mut score = model.score(&plaintext);
+
+        for step in 0..options.iterations {
+            let a = rng.below(size);
+            let b = rng.below(size);
+            if a == b {
+                continue;
+            }
+            key.swap(a, b);
+            decrypt_with(&key, &text, &mut plaintext);
+            let new_score = model.score(&plaintext);
+
+            let temperature = options.temperature *
+                              (1.0 - step as f64 / options.iterations as f64);
+            let delta = new_score - score;
+            if delta >= 0.0 || (temperature > 0.0 && rng.unit() < (delta / temperature).exp()) {
+                score = new_score;
+                if score > best_score {
+                    best_score = score;
+                    best_key = key.clone();
+                }
+            } else {
+                key.swap(a, b);
+            }
+        }
+        debug!("Restart {} finished with best score {:.2}", restart, best_score);
+    }
+
+    // Turn the decryption key around to get the encryption permutation.
+    let mut permutation = vec![EncodeNum::from(0); size];
+    for (c, p) in best_key.iter().enumerate() {
+        permutation[*p] = EncodeNum::from(c as u64);
+    }
+    let key: String = try!(permutation.iter().map(|n| encoding.number_to_char(n)).collect());
+    decrypt_with(&best_key, &text, &mut plaintext);
+    let plaintext: String = try!(plaintext.iter()
+        .map(|p| encoding.number_to_char(&EncodeNum::from(*p as u64)))
+        .collect());
+
+    Ok(Candidate {
+        key: key,
+        plaintext: plaintext,
+        score: best_score,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Language, NgramModel};
+    use super::super::super::{alpha, alpha_space, Error};
+    use super::super::super::cipher::{Cipher, Substitution};
+
+    const PLAINTEXT: &'static str = "it was the best of times it was the worst of times it was \
+                                     the age of wisdom it was the age of foolishness it was the \
+                                     epoch of belief it was the epoch of incredulity it was the \
+                                     season of light it was the season of darkness it was the \
+                                     spring of hope it was the winter of despair we had \
+                                     everything before us we had nothing before us we were all \
+                                     going direct to heaven we were all going direct the other \
+                                     way in short the period was so far like the present period \
+                                     that some of its noisiest authorities insisted on its being \
+                                     received for good or for evil in the superlative degree of \
+                                     comparison only";
+
+    fn encrypt(e: &Encoding, s: &Substitution, text: &str) -> String {
+        let m = e.vectorize_string(text).unwrap();
+        s.encrypt(e, &m).unwrap().iter().map(|n| e.number_to_char(n).unwrap()).collect()
+    }
+
+    fn options() -> AnnealOptions {
+        AnnealOptions {
+            restarts: 3,
+            iterations: 8000,
+            ..AnnealOptions::default()
+        }
+    }
+
+    #[test]
+    fn frequency_key_lines_up_counts() {
+        let key = frequency_key(&[2, 2, 2, 0, 0, 1], &[0.5, 0.3, 0.2]);
+        assert_eq!(key, vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn frequency_key_survives_nan() {
+        let key = frequency_key(&[0, 0, 1], &[::std::f64::NAN, 0.3, 0.2]);
+        assert_eq!(key.len(), 3);
+    }
+
+    #[test]
+    fn reject_empty_encoding() {
+        let model = NgramModel::english(&alpha()).unwrap();
+        match crack_substitution(&Encoding::new(), "", &Language::english(), &model, &options()) {
+            Err(Error::EmptyEncoding) => (),
+            Ok(_) => panic!("We cracked a message with no alphabet!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn crack_alpha() {
+        let e = alpha();
+        let plaintext = e.map_filter_string(PLAINTEXT);
+        let s = Substitution::from_keyword(&e, "zebras").unwrap();
+        let ciphertext = encrypt(&e, &s, &plaintext);
+        let model = NgramModel::english(&e).unwrap();
+        let candidate =
+            crack_substitution(&e, &ciphertext, &Language::english(), &model, &options()).unwrap();
+        assert_eq!(candidate.plaintext, plaintext);
+        assert_eq!(encrypt(&e,
+                           &Substitution::from_permutation(&e, &candidate.key).unwrap(),
+                           &plaintext),
+                   ciphertext);
+    }
+
+    #[test]
+    fn crack_alpha_space() {
+        let e = alpha_space();
+        let s = Substitution::from_keyword(&e, "the quick brown fox").unwrap();
+        let ciphertext = encrypt(&e, &s, PLAINTEXT);
+        let model = NgramModel::english(&e).unwrap();
+        let candidate =
+            crack_substitution(&e, &ciphertext, &Language::english(), &model, &options()).unwrap();
+        assert_eq!(candidate.plaintext, PLAINTEXT);
+    }
+}
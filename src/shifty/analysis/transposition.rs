@@ -0,0 +1,152 @@
+//! Breaking the fixed-pattern transpositions by trying every key.
+//!
+//! Transposition leaves the symbol counts alone, so single letter
+//! frequencies can not tell the keys apart. Each decryption is scored
+//! with an n-gram model instead, which notices when neighbouring
+//! symbols line up into words.
+
+use std::cmp;
+use super::{to_indices, Candidate, NgramModel};
+use super::super::{EncodeNum, Encoding, Result};
+use super::super::cipher::{Cipher, RailFence, Route, RoutePath, Scytale};
+
+/// Most rails a rail fence is tried with.
+pub const MAX_RAILS: usize = 32;
+/// Widest grid a scytale or route cipher is tried with.
+pub const MAX_WIDTH: usize = 100;
+
+fn score_decryption(encoding: &Encoding,
+                    text: &[EncodeNum],
+                    model: &NgramModel,
+                    cipher: &Cipher,
+                    key: String)
+                    -> Result<Candidate> {
+    let plaintext = try!(cipher.decrypt(encoding, text));
+    Ok(Candidate {
+        key: key,
+        plaintext: try!(plaintext.iter().map(|n| encoding.number_to_char(n)).collect()),
+        score: model.score(&to_indices(&plaintext)),
+    })
+}
+
+fn ranked(mut candidates: Vec<Candidate>) -> Vec<Candidate> {
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+    candidates
+}
+
+/// Every rail count up to `MAX_RAILS`, at every offset into its zigzag.
+pub fn crack_rail_fence(encoding: &Encoding,
+                        ciphertext: &str,
+                        model: &NgramModel)
+                        -> Result<Vec<Candidate>> {
+    let text = try!(encoding.vectorize_string(ciphertext));
+    let mut candidates = Vec::new();
+    for rails in 2..cmp::min(text.len(), MAX_RAILS) + 1 {
+        let period = try!(RailFence::new(rails, 0)).period();
+        for offset in 0..period {
+            let cipher = try!(RailFence::new(rails, offset));
+            let key = format!("rails {} offset {}", rails, offset);
+            candidates.push(try!(score_decryption(encoding, &text, model, &cipher, key)));
+        }
+    }
+    Ok(ranked(candidates))
+}
+
+/// Every diameter up to `MAX_WIDTH`.
+pub fn crack_scytale(encoding: &Encoding,
+                     ciphertext: &str,
+                     model: &NgramModel)
+                     -> Result<Vec<Candidate>> {
+    let text = try!(encoding.vectorize_string(ciphertext));
+    let mut candidates = Vec::new();
+    for diameter in 2..cmp::min(text.len(), MAX_WIDTH) + 1 {
+        let cipher = try!(Scytale::new(diameter));
+        let key = format!("diameter {}", diameter);
+        candidates.push(try!(score_decryption(encoding, &text, model, &cipher, key)));
+    }
+    Ok(ranked(candidates))
+}
+
+/// Every grid width up to `MAX_WIDTH`, along every path.
+pub fn crack_route(encoding: &Encoding,
+                   ciphertext: &str,
+                   model: &NgramModel)
+                   -> Result<Vec<Candidate>> {
+    let text = try!(encoding.vectorize_string(ciphertext));
+    let mut candidates = Vec::new();
+    for columns in 2..cmp::min(text.len(), MAX_WIDTH) + 1 {
+        for path in &[RoutePath::Spiral, RoutePath::Boustrophedon] {
+            let cipher = try!(Route::new(columns, *path));
+            let key = format!("{:?} columns {}", path, columns).to_lowercase();
+            candidates.push(try!(score_decryption(encoding, &text, model, &cipher, key)));
+        }
+    }
+    Ok(ranked(candidates))
+}
+
+/// All of the above, ranked together.
+pub fn crack_transposition(encoding: &Encoding,
+                           ciphertext: &str,
+                           model: &NgramModel)
+                           -> Result<Vec<Candidate>> {
+    let mut candidates = try!(crack_rail_fence(encoding, ciphertext, model));
+    candidates.extend(try!(crack_scytale(encoding, ciphertext, model)));
+    candidates.extend(try!(crack_route(encoding, ciphertext, model)));
+    Ok(ranked(candidates))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::NgramModel;
+    use super::super::super::{alpha_space, Encoding, Error};
+    use super::super::super::cipher::{Cipher, RailFence, Route, RoutePath, Scytale};
+
+    const PLAINTEXT: &'static str = "it was the best of times it was the worst of times it was \
+                                     the age of wisdom it was the age of foolishness";
+
+    fn encrypt(e: &Encoding, cipher: &Cipher) -> String {
+        let m = e.vectorize_string(PLAINTEXT).unwrap();
+        cipher.encrypt(e, &m).unwrap().iter().map(|n| e.number_to_char(n).unwrap()).collect()
+    }
+
+    #[test]
+    fn crack_rail_fence_with_offset() {
+        let e = alpha_space();
+        let model = NgramModel::english(&e).unwrap();
+        let ciphertext = encrypt(&e, &RailFence::new(5, 3).unwrap());
+        let best = &crack_rail_fence(&e, &ciphertext, &model).unwrap()[0];
+        assert_eq!(best.key, "rails 5 offset 3");
+        assert_eq!(best.plaintext, PLAINTEXT);
+    }
+
+    #[test]
+    fn crack_any_transposition() {
+        let e = alpha_space();
+        let model = NgramModel::english(&e).unwrap();
+        let ciphertext = encrypt(&e, &Scytale::new(7).unwrap());
+        assert_eq!(crack_transposition(&e, &ciphertext, &model).unwrap()[0].plaintext,
+                   PLAINTEXT);
+        let ciphertext = encrypt(&e, &Route::new(9, RoutePath::Spiral).unwrap());
+        let best = &crack_transposition(&e, &ciphertext, &model).unwrap()[0];
+        assert_eq!(best.key, "spiral columns 9");
+        assert_eq!(best.plaintext, PLAINTEXT);
+    }
+
+    #[test]
+    fn reject_empty_encoding() {
+        let e = Encoding::new();
+        match NgramModel::english(&e).and_then(|model| crack_transposition(&e, "", &model)) {
+            Err(Error::EmptyEncoding) => (),
+            Ok(_) => panic!("We cracked a message with no alphabet!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn no_keys_for_one_symbol() {
+        let e = alpha_space();
+        let model = NgramModel::english(&e).unwrap();
+        assert!(crack_transposition(&e, "a", &model).unwrap().is_empty());
+    }
+}
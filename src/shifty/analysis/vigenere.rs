@@ -0,0 +1,238 @@
+//! Breaking Vigenère ciphertext with frequency analysis.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use super::{counts, to_indices, Candidate, Language};
+use super::super::{EncodeNum, Encoding, Error, Result};
+
+/// Smallest run of repeated symbols Kasiski examination looks for.
+const KASISKI_MIN_RUN: usize = 3;
+
+/// Chance that two symbols drawn from `text` are the same.
+pub fn index_of_coincidence(text: &[EncodeNum], size: usize) -> f64 {
+    ic_of_indices(&to_indices(text), size)
+}
+
+fn ic_of_indices(text: &[usize], size: usize) -> f64 {
+    let n = text.len();
+    if n < 2 {
+        return 0.0;
+    }
+    let pairs: usize = counts(text, size).iter().map(|c| c * c.saturating_sub(1)).sum();
+    pairs as f64 / (n * (n - 1)) as f64
+}
+
+/// The index of coincidence we would expect from plain text.
+fn language_ic(expected: &[f64]) -> f64 {
+    expected.iter().map(|p| p * p).sum()
+}
+
+/// Friedman's estimate of the key length, from how far the ciphertext's
+/// index of coincidence sits between random text and plain text.
+pub fn friedman_estimate(text: &[EncodeNum], expected: &[f64]) -> f64 {
+    let size = expected.len();
+    let random_ic = 1.0 / size as f64;
+    let plain_ic = language_ic(expected);
+    let observed_ic = index_of_coincidence(text, size);
+    if observed_ic <= random_ic {
+        return text.len() as f64;
+    }
+    (plain_ic - random_ic) / (observed_ic - random_ic)
+}
+
+/// For every key length in `1..max_key_length + 1`, the fraction of
+/// distances between repeated runs of symbols that it divides evenly.
+/// Index `0` is unused.
+pub fn kasiski_examination(text: &[EncodeNum], max_key_length: usize) -> Vec<f64> {
+    let mut last_seen: HashMap<&[EncodeNum], usize> = HashMap::new();
+    let mut distances = Vec::new();
+    if text.len() >= KASISKI_MIN_RUN {
+        for i in 0..(text.len() - KASISKI_MIN_RUN + 1) {
+            let run = &text[i..i + KASISKI_MIN_RUN];
+            if let Some(previous) = last_seen.insert(run, i) {
+                distances.push(i - previous);
+            }
+        }
+    }
+
+    let mut fractions = vec![0.0; max_key_length + 1];
+    if distances.is_empty() {
+        return fractions;
+    }
+    for length in 2..(max_key_length + 1) {
+        let hits = distances.iter().filter(|d| *d % length == 0).count();
+        fractions[length] = hits as f64 / distances.len() as f64;
+    }
+    fractions
+}
+
+fn column(text: &[usize], key_length: usize, offset: usize) -> Vec<usize> {
+    text.iter().skip(offset).step_by(key_length).cloned().collect()
+}
+
+fn chi_squared(observed: &[usize], expected: &[f64], total: usize) -> f64 {
+    observed.iter()
+        .zip(expected)
+        .map(|(o, p)| {
+            let e = p * total as f64;
+            (*o as f64 - e) * (*o as f64 - e) / e
+        })
+        .sum()
+}
+
+/// The shift which makes `column` look most like the language.
+fn best_shift(column: &[usize], expected: &[f64]) -> usize {
+    let size = expected.len();
+    let observed = counts(column, size);
+    let mut best = (0, ::std::f64::INFINITY);
+    for shift in 0..size {
+        // Decrypting by `shift` moves ciphertext symbol `c` to `c - shift`,
+        // so plaintext symbol `p` was counted at `p + shift`.
+        let shifted: Vec<usize> = (0..size).map(|p| observed[(p + shift) % size]).collect();
+        let chi = chi_squared(&shifted, expected, column.len());
+        if chi < best.1 {
+            best = (shift, chi);
+        }
+    }
+    best.0
+}
+
+/// Shortest key that repeats to give `key`.
+fn reduce_key(key: &[usize]) -> &[usize] {
+    for period in 1..key.len() {
+        if key.len() % period == 0 && (period..key.len()).all(|i| key[i] == key[i - period]) {
+            return &key[..period];
+        }
+    }
+    key
+}
+
+/// Break Vigenère ciphertext, returning the most likely keys first.
+///
+/// Each key length up to `max_key_length` is scored by the average index of
+/// coincidence of its columns, with a boost from Kasiski examination and
+/// from being near Friedman's estimate. The key for each length is then
+/// recovered one column at a time by chi-squared against `language`.
+pub fn crack_vigenere(encoding: &Encoding,
+                      ciphertext: &str,
+                      language: &Language,
+                      max_key_length: usize)
+                      -> Result<Vec<Candidate>> {
+    if encoding.size() == 0 {
+        return Err(Error::EmptyEncoding);
+    }
+    let text = try!(encoding.vectorize_string(ciphertext));
+    let expected = try!(language.expected(encoding));
+    let size = encoding.size();
+    let indices = to_indices(&text);
+    let plain_ic = language_ic(&expected);
+
+    let max_key_length = ::std::cmp::max(1, ::std::cmp::min(max_key_length, text.len() / 2));
+    let kasiski = kasiski_examination(&text, max_key_length);
+    let friedman = friedman_estimate(&text, &expected);
+    debug!("Friedman estimate: {:.2}", friedman);
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+    for key_length in 1..(max_key_length + 1) {
+        let columns: Vec<Vec<usize>> =
+            (0..key_length).map(|i| column(&indices, key_length, i)).collect();
+        let average_ic = columns.iter().map(|c| ic_of_indices(c, size)).sum::<f64>() /
+                         key_length as f64;
+        let score = average_ic / plain_ic + 0.5 * kasiski[key_length] +
+                    0.1 / (1.0 + (key_length as f64 - friedman).abs());
+        trace!("Key length {}: ic {:.4} score {:.4}",
+               key_length,
+               average_ic,
+               score);
+
+        let shifts: Vec<usize> = columns.iter().map(|c| best_shift(c, &expected)).collect();
+        let key: String = try!(reduce_key(&shifts)
+            .iter()
+            .map(|s| encoding.number_to_char(&EncodeNum::from(*s as u64)))
+            .collect());
+
+        if let Some(existing) = candidates.iter_mut().find(|c| c.key == key) {
+            if score > existing.score {
+                existing.score = score;
+            }
+            continue;
+        }
+        let plaintext = try!(encoding.decrypt(ciphertext, &key));
+        candidates.push(Candidate {
+            key: key,
+            plaintext: plaintext,
+            score: score,
+        });
+    }
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Language;
+    use super::super::super::{alpha, alphanumeric_space, EncodeNum, Encoding, Error};
+
+    const PLAINTEXT: &'static str = "it was the best of times it was the worst of times it was \
+                                     the age of wisdom it was the age of foolishness it was the \
+                                     epoch of belief it was the epoch of incredulity it was the \
+                                     season of light it was the season of darkness it was the \
+                                     spring of hope it was the winter of despair we had \
+                                     everything before us we had nothing before us we were all \
+                                     going direct to heaven we were all going direct the other \
+                                     way in short the period was so far like the present period \
+                                     that some of its noisiest authorities insisted on its being \
+                                     received for good or for evil in the superlative degree of \
+                                     comparison only";
+
+    #[test]
+    fn ic_of_repeated_symbol_is_one() {
+        let text = vec![EncodeNum::from(3); 10];
+        assert_eq!(index_of_coincidence(&text, 26), 1.0);
+    }
+
+    #[test]
+    fn kasiski_finds_repeat_distance() {
+        let e = alpha();
+        let text = e.vectorize_string("abcdefgabchijkabc").unwrap();
+        let fractions = kasiski_examination(&text, 8);
+        assert_eq!(fractions[7], 1.0);
+        assert_eq!(fractions[2], 0.0);
+    }
+
+    #[test]
+    fn reduce_repeated_key() {
+        assert_eq!(reduce_key(&[1, 2, 1, 2, 1, 2]), &[1, 2]);
+        assert_eq!(reduce_key(&[1, 2, 1]), &[1, 2, 1]);
+    }
+
+    #[test]
+    fn crack_alpha() {
+        let e = alpha();
+        let plaintext = e.map_filter_string(PLAINTEXT);
+        let ciphertext = e.encrypt(&plaintext, "lemon").unwrap();
+        let candidates = crack_vigenere(&e, &ciphertext, &Language::english(), 20).unwrap();
+        assert_eq!(candidates[0].key, "lemon");
+        assert_eq!(candidates[0].plaintext, plaintext);
+    }
+
+    #[test]
+    fn crack_alphanumeric_space() {
+        let e = alphanumeric_space();
+        let ciphertext = e.encrypt(PLAINTEXT, "secret 42").unwrap();
+        let candidates = crack_vigenere(&e, &ciphertext, &Language::english(), 20).unwrap();
+        assert_eq!(candidates[0].key, "secret 42");
+        assert_eq!(candidates[0].plaintext, PLAINTEXT);
+    }
+
+    #[test]
+    fn reject_empty_encoding() {
+        match crack_vigenere(&Encoding::new(), "", &Language::english(), 20) {
+            Err(Error::EmptyEncoding) => (),
+            Ok(_) => panic!("We cracked a message with no alphabet!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
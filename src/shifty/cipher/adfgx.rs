@@ -0,0 +1,89 @@
+//! The ADFGX and ADFGVX ciphers: each symbol is written as its pair of
+//! coordinates in a keyed Polybius square, and the coordinates are then
+//! mixed up by a columnar transposition.
+
+use super::Cipher;
+use super::columnar::Columnar;
+use super::polybius::{Coordinates, Polybius, PolybiusSquare};
+use super::super::{EncodeNum, Encoding, Result};
+
+#[derive(Debug, Clone)]
+pub struct Adfgx {
+    polybius: Polybius,
+    transposition: Columnar,
+}
+
+impl Adfgx {
+    /// The square is filled from `square_keyword`, its rows and columns
+    /// are labelled with `coordinates`, and the transposition is keyed
+    /// with `transposition_keyword`.
+    pub fn new(encoding: &Encoding,
+               square_keyword: &str,
+               coordinates: &str,
+               transposition_keyword: &str)
+               -> Result<Adfgx> {
+        let square = try!(PolybiusSquare::new(encoding, square_keyword));
+        let coordinates = try!(Coordinates::new(encoding, coordinates, square.side()));
+        Ok(Adfgx {
+            polybius: Polybius::new(square, coordinates),
+            transposition: try!(Columnar::new(encoding, transposition_keyword, "")),
+        })
+    }
+
+    /// The 5x5 version, for the `playfair` encoding.
+    pub fn adfgx(encoding: &Encoding,
+                 square_keyword: &str,
+                 transposition_keyword: &str)
+                 -> Result<Adfgx> {
+        Adfgx::new(encoding, square_keyword, "adfgx", transposition_keyword)
+    }
+
+    /// The 6x6 version with digits, for the `playfair_6x6` encoding.
+    pub fn adfgvx(encoding: &Encoding,
+                  square_keyword: &str,
+                  transposition_keyword: &str)
+                  -> Result<Adfgx> {
+        Adfgx::new(encoding, square_keyword, "adfgvx", transposition_keyword)
+    }
+}
+
+impl Cipher for Adfgx {
+    fn encrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        self.transposition.encrypt(encoding, &self.polybius.fractionate(message))
+    }
+
+    fn decrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        let coordinates = try!(self.transposition.decrypt(encoding, message));
+        self.polybius.unfractionate(encoding, &coordinates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{playfair, playfair_6x6, Error};
+
+    #[test]
+    fn adfgx_vector() {
+        let e = playfair();
+        let a = Adfgx::adfgx(&e, "btalpdhozkqfvsngicuxmrewy", "cargo").unwrap();
+        harness::known_vector(&a, &e, "attackatonce", "faxdfadddgdgfffafaxafafx");
+    }
+
+    #[test]
+    fn adfgvx_vector() {
+        let e = playfair_6x6();
+        let a = Adfgx::adfgvx(&e, "na1c3h8tb2ome5wrpd4f6g7i9j0klqsuvxyz", "privacy").unwrap();
+        harness::known_vector(&a, &e, "attackat1200am", "dgdddagddgafaddfdadvdvfaadvx");
+    }
+
+    #[test]
+    fn adfgvx_needs_six_coordinates() {
+        match Adfgx::adfgvx(&playfair(), "keyword", "privacy") {
+            Err(Error::WrongCoordinates(5, 6)) => (),
+            Ok(_) => panic!("We labelled five rows with six letters!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! The affine cipher, `c = a * m + b (mod n)`.
+
+use super::{Cipher, SymbolTransform};
+use super::super::{Action, EncodeNum, Encoding, Error, Result};
+use super::super::modular;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Affine {
+    a: EncodeNum,
+    a_inverse: EncodeNum,
+    b: EncodeNum,
+    size: EncodeNum,
+}
+
+impl Affine {
+    /// The multiplier `a` needs an inverse mod the size of `encoding`,
+    /// which means it has to be coprime to it.
+    pub fn new(encoding: &Encoding, a: u64, b: u64) -> Result<Affine> {
+        let size = encoding.size() as u64;
+        if size == 0 {
+            return Err(Error::EmptyEncoding);
+        }
+        if modular::gcd(a, size) != 1 {
+            return Err(Error::NotCoprime(a, encoding.size()));
+        }
+        let a = EncodeNum::from(a % size);
+        // Coprime to the size, so the inverse is always there.
+        let a_inverse = a.inverse_mod(size).unwrap();
+        Ok(Affine {
+            a: a,
+            a_inverse: a_inverse,
+            b: EncodeNum::from(b % size),
+            size: EncodeNum::from(size),
+        })
+    }
+
+    fn encrypt_num(&self, m: EncodeNum) -> EncodeNum {
+        (self.a * m + self.b) % self.size
+    }
+
+    fn decrypt_num(&self, c: EncodeNum) -> EncodeNum {
+        (self.a_inverse * (c + self.size - self.b)) % self.size
+    }
+}
+
+struct AffineState {
+    affine: Affine,
+    action: Action,
+}
+
+impl SymbolTransform for AffineState {
+    fn transform(&mut self, symbol: EncodeNum) -> Result<EncodeNum> {
+        Ok(match self.action {
+            Action::Encrypt => self.affine.encrypt_num(symbol),
+            Action::Decrypt => self.affine.decrypt_num(symbol),
+        })
+    }
+}
+
+impl Cipher for Affine {
+    fn encrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(message.iter().map(|m| self.encrypt_num(*m)).collect())
+    }
+
+    fn decrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(message.iter().map(|c| self.decrypt_num(*c)).collect())
+    }
+
+    fn symbolwise(&self, _encoding: &Encoding, action: Action) -> Option<Box<SymbolTransform>> {
+        Some(Box::new(AffineState {
+            affine: *self,
+            action: action,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{alpha, alphanumeric_space, Error};
+
+    #[test]
+    fn affine_vector() {
+        let e = alpha();
+        let a = Affine::new(&e, 5, 8).unwrap();
+        harness::known_vector(&a, &e, "affinecipher", "ihhwvcswfrcp");
+    }
+
+    #[test]
+    fn custom_alphabet() {
+        let e = alphanumeric_space();
+        let a = Affine::new(&e, 7, 20).unwrap();
+        harness::roundtrip(&a, &e, "meet me at the 4th gate at 9");
+    }
+
+    #[test]
+    fn multiplier_must_be_coprime() {
+        let e = alpha();
+        match Affine::new(&e, 13, 1) {
+            Err(Error::NotCoprime(13, 26)) => (),
+            Ok(_) => panic!("We accepted a multiplier with no inverse!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+        // The error names the multiplier as given, not reduced mod 26.
+        match Affine::new(&e, 39, 1) {
+            Err(Error::NotCoprime(39, 26)) => (),
+            Ok(_) => panic!("We accepted a multiplier with no inverse!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Autokey Vigenère, where the keystream is the key
+//! followed by the plaintext itself.
+
+use std::collections::VecDeque;
+use super::{Cipher, SymbolTransform};
+use super::vigenere::transform;
+use super::super::{Action, EncodeNum, Encoding, Error, Result};
+
+#[derive(Debug)]
+pub struct Autokey {
+    key: Vec<EncodeNum>,
+}
+
+impl Autokey {
+    pub fn new(key: Vec<EncodeNum>) -> Result<Autokey> {
+        try!(super::require_key(&key));
+        Ok(Autokey { key: key })
+    }
+
+    pub fn from_key(encoding: &Encoding, keytext: &str) -> Result<Autokey> {
+        Autokey::new(try!(encoding.vectorize_string(keytext)))
+    }
+}
+
+struct AutokeyState {
+    /// Upcoming keystream symbols. Each plaintext symbol joins the back
+    /// as the front is used up, so this never grows past the key length.
+    keystream: VecDeque<EncodeNum>,
+    size: usize,
+    action: Action,
+}
+
+impl SymbolTransform for AutokeyState {
+    fn transform(&mut self, symbol: EncodeNum) -> Result<EncodeNum> {
+        let key_num = self.keystream.pop_front().unwrap();
+        let result = transform(&symbol, &key_num, &self.size, &self.action);
+        let plain_num = match self.action {
+            Action::Encrypt => symbol,
+            Action::Decrypt => result,
+        };
+        self.keystream.push_back(plain_num);
+        trace!("{:?} m({}) k({}) -> c({})",
+               self.action,
+               symbol,
+               key_num,
+               result);
+        Ok(result)
+    }
+
+    /// The keystream is made of plaintext, so a copied character has no
+    /// key symbol of its own to use up.
+    fn skip(&mut self) -> Result<()> {
+        Err(Error::CannotAdvanceKey)
+    }
+}
+
+impl Cipher for Autokey {
+    fn encrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        super::transform_all(self.symbolwise(encoding, Action::Encrypt).unwrap(), message)
+    }
+
+    fn decrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        super::transform_all(self.symbolwise(encoding, Action::Decrypt).unwrap(), message)
+    }
+
+    fn symbolwise(&self, encoding: &Encoding, action: Action) -> Option<Box<SymbolTransform>> {
+        Some(Box::new(AutokeyState {
+            keystream: self.key.iter().cloned().collect(),
+            size: encoding.size(),
+            action: action,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{alpha, alphanumeric_space, Action, Error, UnknownChars};
+
+    #[test]
+    fn attack_at_dawn() {
+        let e = alpha();
+        let a = Autokey::from_key(&e, "queenly").unwrap();
+        harness::known_vector(&a, &e, "attackatdawn", "qnxepvytwtwp");
+    }
+
+    #[test]
+    fn message_shorter_than_key() {
+        let e = alpha();
+        let a = Autokey::from_key(&e, "queenly").unwrap();
+        harness::known_vector(&a, &e, "att", "qnx");
+    }
+
+    #[test]
+    fn custom_alphabet() {
+        let e = alphanumeric_space();
+        let a = Autokey::from_key(&e, "k3y").unwrap();
+        harness::roundtrip(&a, &e, "meet me at the 4th gate at 9");
+    }
+
+    #[test]
+    fn refuse_to_advance_key() {
+        let e = alpha();
+        let a = Autokey::from_key(&e, "queenly").unwrap();
+        let unknown = UnknownChars::Passthrough { advance_key: true };
+        let mut c: Vec<u8> = Vec::new();
+        match e.transform_stream("at dawn".as_bytes(), &mut c, &a, Action::Encrypt, unknown) {
+            Err(Error::CannotAdvanceKey) => (),
+            Ok(_) => panic!("We advanced a key made of plaintext!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
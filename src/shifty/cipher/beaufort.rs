@@ -0,0 +1,135 @@
+//! Beaufort (`c = k - m`) and Variant Beaufort (`c = m - k`),
+//! both with a repeating key like Vigenère or any other `Keystream`.
+
+use super::{Cipher, SymbolTransform, Vigenere};
+use super::keystream::{Keystream, KeystreamState, Repeating};
+use super::super::{Action, EncodeNum, Encoding, Result};
+
+/// Subtract the message from the key. Doing it twice gets the message back,
+/// so this is used for both encryption and decryption.
+pub fn reciprocal(message: &EncodeNum, key: &EncodeNum, size: &usize) -> EncodeNum {
+    let s = *size as i64;
+    let m = u64::from(*message) as i64;
+    let k = u64::from(*key) as i64;
+    let c = (k - m + s) % s;
+    EncodeNum::from(c as u64)
+}
+
+#[derive(Debug)]
+pub struct Beaufort {
+    keystream: Box<Keystream>,
+}
+
+impl Beaufort {
+    pub fn new(key: Vec<EncodeNum>) -> Result<Beaufort> {
+        Ok(Beaufort::with_keystream(Box::new(try!(Repeating::new(key)))))
+    }
+
+    pub fn from_key(encoding: &Encoding, keytext: &str) -> Result<Beaufort> {
+        Beaufort::new(try!(encoding.vectorize_string(keytext)))
+    }
+
+    pub fn with_keystream(keystream: Box<Keystream>) -> Beaufort {
+        Beaufort { keystream: keystream }
+    }
+}
+
+impl Cipher for Beaufort {
+    fn encrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        super::transform_all(self.symbolwise(encoding, Action::Encrypt).unwrap(), message)
+    }
+
+    fn decrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        super::transform_all(self.symbolwise(encoding, Action::Decrypt).unwrap(), message)
+    }
+
+    fn symbolwise(&self, encoding: &Encoding, _action: Action) -> Option<Box<SymbolTransform>> {
+        let size = encoding.size();
+        let combine = Box::new(reciprocal);
+        Some(Box::new(KeystreamState::new(&*self.keystream, size, combine, "Beaufort")))
+    }
+}
+
+/// Vigenère run backwards: encrypting subtracts the key
+/// and decrypting adds it.
+#[derive(Debug)]
+pub struct VariantBeaufort {
+    vigenere: Vigenere,
+}
+
+impl VariantBeaufort {
+    pub fn new(key: Vec<EncodeNum>) -> Result<VariantBeaufort> {
+        Ok(VariantBeaufort { vigenere: try!(Vigenere::new(key)) })
+    }
+
+    pub fn from_key(encoding: &Encoding, keytext: &str) -> Result<VariantBeaufort> {
+        VariantBeaufort::new(try!(encoding.vectorize_string(keytext)))
+    }
+
+    pub fn with_keystream(keystream: Box<Keystream>) -> VariantBeaufort {
+        VariantBeaufort { vigenere: Vigenere::with_keystream(keystream) }
+    }
+}
+
+impl Cipher for VariantBeaufort {
+    fn encrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        self.vigenere.decrypt(encoding, message)
+    }
+
+    fn decrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        self.vigenere.encrypt(encoding, message)
+    }
+
+    fn symbolwise(&self, encoding: &Encoding, action: Action) -> Option<Box<SymbolTransform>> {
+        let reversed = match action {
+            Action::Encrypt => Action::Decrypt,
+            Action::Decrypt => Action::Encrypt,
+        };
+        self.vigenere.symbolwise(encoding, reversed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{alpha, alphanumeric_space, Action};
+
+    #[test]
+    fn beaufort_vector() {
+        let e = alpha();
+        let b = Beaufort::from_key(&e, "fortification").unwrap();
+        harness::known_vector(&b,
+                              &e,
+                              "defendtheeastwallofthecastle",
+                              "ckmpvcpvwpiwujogiuapvwriwuuk");
+    }
+
+    #[test]
+    fn beaufort_is_self_reciprocal() {
+        let e = alphanumeric_space();
+        let b = Beaufort::from_key(&e, "k3y").unwrap();
+        let m = e.vectorize_string("meet me at 9").unwrap();
+        let c = b.encrypt(&e, &m).unwrap();
+        assert_eq!(b.encrypt(&e, &c).unwrap(), m);
+        assert_eq!(e.transform_with("meet me at 9", &b, Action::Encrypt).unwrap(),
+                   e.transform_with("meet me at 9", &b, Action::Decrypt).unwrap());
+    }
+
+    #[test]
+    fn variant_beaufort_vector() {
+        let e = alpha();
+        let b = VariantBeaufort::from_key(&e, "fortification").unwrap();
+        harness::known_vector(&b,
+                              &e,
+                              "defendtheeastwallofthecastle",
+                              "yqolfylfelsegrmusgalfejseggq");
+    }
+
+    #[test]
+    fn variant_beaufort_roundtrip() {
+        let e = alphanumeric_space();
+        let b = VariantBeaufort::from_key(&e, "k3y").unwrap();
+        harness::roundtrip(&b, &e, "meet me at the 4th gate at 9");
+    }
+}
@@ -0,0 +1,147 @@
+//! Delastelle's Bifid and Trifid ciphers.
+//!
+//! Each symbol is split into its coordinates in a Polybius square (or
+//! cube for Trifid). Within each period the coordinates are written out
+//! one axis at a time, first all the rows and then all the columns, and
+//! read back in groups to give the ciphertext symbols.
+
+use super::Cipher;
+use super::polybius::PolybiusSquare;
+use super::super::{Action, EncodeNum, Encoding, Result};
+
+fn fractionate(square: &PolybiusSquare,
+               period: usize,
+               message: &[EncodeNum],
+               action: Action)
+               -> Vec<EncodeNum> {
+    let dimensions = square.dimensions();
+    let period = if period == 0 { message.len() } else { period };
+    let mut output = Vec::with_capacity(message.len());
+    for block in message.chunks(::std::cmp::max(period, 1)) {
+        let n = block.len();
+        let coordinates: Vec<Vec<usize>> = block.iter().map(|m| square.coordinates(*m)).collect();
+        // All the coordinates of the block, axis by axis.
+        let mut by_axis = vec![0; n * dimensions];
+        match action {
+            Action::Encrypt => {
+                for (i, c) in coordinates.iter().enumerate() {
+                    for axis in 0..dimensions {
+                        by_axis[axis * n + i] = c[axis];
+                    }
+                }
+                output.extend(by_axis.chunks(dimensions).map(|c| square.symbol(c)));
+            }
+            Action::Decrypt => {
+                for (i, c) in coordinates.iter().enumerate() {
+                    for axis in 0..dimensions {
+                        by_axis[i * dimensions + axis] = c[axis];
+                    }
+                }
+                output.extend((0..n).map(|i| {
+                    let c: Vec<usize> = (0..dimensions).map(|axis| by_axis[axis * n + i]).collect();
+                    square.symbol(&c)
+                }));
+            }
+        }
+    }
+    output
+}
+
+/// Fractionation on a square, `period` symbols at a time. A period of
+/// zero takes the whole message at once.
+#[derive(Debug, Clone)]
+pub struct Bifid {
+    square: PolybiusSquare,
+    period: usize,
+}
+
+impl Bifid {
+    pub fn new(encoding: &Encoding, keyword: &str, period: usize) -> Result<Bifid> {
+        Ok(Bifid {
+            square: try!(PolybiusSquare::new(encoding, keyword)),
+            period: period,
+        })
+    }
+}
+
+impl Cipher for Bifid {
+    fn encrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(fractionate(&self.square, self.period, message, Action::Encrypt))
+    }
+
+    fn decrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(fractionate(&self.square, self.period, message, Action::Decrypt))
+    }
+}
+
+/// Fractionation on a 3x3x3 cube, so the encoding needs 27 symbols,
+/// such as `alpha_space`.
+#[derive(Debug, Clone)]
+pub struct Trifid {
+    cube: PolybiusSquare,
+    period: usize,
+}
+
+impl Trifid {
+    pub fn new(encoding: &Encoding, keyword: &str, period: usize) -> Result<Trifid> {
+        Ok(Trifid {
+            cube: try!(PolybiusSquare::cube(encoding, keyword)),
+            period: period,
+        })
+    }
+}
+
+impl Cipher for Trifid {
+    fn encrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(fractionate(&self.cube, self.period, message, Action::Encrypt))
+    }
+
+    fn decrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(fractionate(&self.cube, self.period, message, Action::Decrypt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{alpha, alpha_space, playfair, Error};
+
+    #[test]
+    fn bifid_vector() {
+        let e = playfair();
+        let b = Bifid::new(&e, "bgwkzqpndsioaxefclumthyvr", 0).unwrap();
+        harness::known_vector(&b, &e, "fleeatonce", "uaeolwrins");
+    }
+
+    #[test]
+    fn bifid_period() {
+        let e = playfair();
+        let b = Bifid::new(&e, "keyword", 5).unwrap();
+        harness::roundtrip(&b, &e, "defendtheeastwallofthecastle");
+    }
+
+    #[test]
+    fn trifid_vector() {
+        let mut e = alpha();
+        e.insert_char('+');
+        let t = Trifid::new(&e, "felixmariedelastelle", 5).unwrap();
+        harness::known_vector(&t, &e, "aidetoilecieltaidera", "fmjfvoissuftfpufeqqc");
+    }
+
+    #[test]
+    fn trifid_with_space() {
+        let e = alpha_space();
+        let t = Trifid::new(&e, "the key", 7).unwrap();
+        harness::roundtrip(&t, &e, "meet me at the north gate");
+    }
+
+    #[test]
+    fn trifid_needs_a_cube() {
+        match Trifid::new(&alpha(), "keyword", 5) {
+            Err(Error::NotACube(26)) => (),
+            Ok(_) => panic!("We built a cube from 26 letters!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
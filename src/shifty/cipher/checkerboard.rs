@@ -0,0 +1,236 @@
+//! The straddling checkerboard, which writes the alphabet of one
+//! `Encoding` as digits of another, and the VIC cipher built on it.
+//!
+//! The top row of the board has a cell for every digit except the
+//! blanks, and gives the most common letters a single digit. Each blank
+//! digit then labels a row of its own, whose symbols are written as the
+//! blank followed by their column. Nothing in the top row starts with a
+//! blank, so the digits can be read back without separators.
+
+use super::{Cipher, SymbolTransform};
+use super::keystream::{Combine, Keystream, KeystreamState};
+use super::vigenere;
+use super::super::{EncodeNum, Encoding, Error, Result};
+
+#[derive(Debug, Clone)]
+pub struct StraddlingCheckerboard {
+    /// Number of digits, the size of the numeric encoding
+    base: usize,
+    /// The digits that stand for each symbol of the alphabet
+    codes: Vec<Vec<EncodeNum>>,
+    /// Which digits start a row of their own
+    blank: Vec<bool>,
+    /// The symbol in each cell, indexed by its code read as a number.
+    /// Codes of one digit take the first `base` cells.
+    cells: Vec<Option<EncodeNum>>,
+}
+
+impl StraddlingCheckerboard {
+    /// The board is filled with `keyword` and then the rest of
+    /// `encoding`, along the top row and then down the rows of the
+    /// `blanks`, in the order they are given. Symbols of `digits` stand
+    /// for the numbers 0, 1, 2... in the order of that encoding.
+    pub fn new(encoding: &Encoding,
+               keyword: &str,
+               digits: &Encoding,
+               blanks: &str)
+               -> Result<StraddlingCheckerboard> {
+        let base = digits.size();
+        let mut rows: Vec<usize> = Vec::new();
+        for d in try!(digits.vectorize_string(blanks)) {
+            let d = u64::from(d) as usize;
+            if !rows.contains(&d) {
+                rows.push(d);
+            }
+        }
+        let mut blank = vec![false; base];
+        for d in &rows {
+            blank[*d] = true;
+        }
+
+        let top = (0..base).filter(|d| !blank[*d]);
+        let lower = rows.iter().flat_map(|r| (0..base).map(move |c| vec![*r, c]));
+        let layout: Vec<Vec<usize>> = top.map(|d| vec![d]).chain(lower).collect();
+        let alphabet = try!(encoding.keyed_alphabet(keyword));
+        if alphabet.len() > layout.len() {
+            return Err(Error::TooManySymbols(alphabet.len(), layout.len()));
+        }
+
+        let mut codes = vec![Vec::new(); alphabet.len()];
+        let mut cells = vec![None; base + base * base];
+        for (symbol, code) in alphabet.iter().zip(layout) {
+            let cell = if code.len() == 1 {
+                code[0]
+            } else {
+                base + code[0] * base + code[1]
+            };
+            cells[cell] = Some(*symbol);
+            codes[u64::from(*symbol) as usize] =
+                code.iter().map(|d| EncodeNum::from(*d as u64)).collect();
+        }
+        Ok(StraddlingCheckerboard {
+            base: base,
+            codes: codes,
+            blank: blank,
+            cells: cells,
+        })
+    }
+
+    /// Number of digits the board is labelled with.
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    /// Write every symbol as its digits.
+    pub fn to_digits(&self, message: &[EncodeNum]) -> Vec<EncodeNum> {
+        message.iter()
+            .flat_map(|m| self.codes[u64::from(*m) as usize].clone())
+            .collect()
+    }
+
+    /// Read digits back into symbols.
+    pub fn from_digits(&self, digits: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        let mut message = Vec::with_capacity(digits.len());
+        let mut digits = digits.iter();
+        while let Some(&first) = digits.next() {
+            let d = u64::from(first) as usize;
+            let (code, cell) = if self.blank[d] {
+                match digits.next() {
+                    Some(&second) => {
+                        (vec![first, second],
+                         self.base + d * self.base + u64::from(second) as usize)
+                    }
+                    None => return Err(Error::NotACode(vec![first])),
+                }
+            } else {
+                (vec![first], d)
+            };
+            match self.cells[cell] {
+                Some(symbol) => message.push(symbol),
+                None => return Err(Error::NotACode(code)),
+            }
+        }
+        Ok(message)
+    }
+}
+
+/// Encrypting reads the alphabet and writes digits,
+/// and decrypting goes the other way.
+impl Cipher for StraddlingCheckerboard {
+    fn encrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(self.to_digits(message))
+    }
+
+    fn decrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        self.from_digits(message)
+    }
+}
+
+/// The message is written out on a checkerboard, and a keystream of
+/// digits is added to it without carrying. The VIC cipher took its
+/// keystream from chain addition, with `ChainAddition`.
+#[derive(Debug)]
+pub struct Vic {
+    checkerboard: StraddlingCheckerboard,
+    keystream: Box<Keystream>,
+}
+
+impl Vic {
+    pub fn new(checkerboard: StraddlingCheckerboard, keystream: Box<Keystream>) -> Vic {
+        Vic {
+            checkerboard: checkerboard,
+            keystream: keystream,
+        }
+    }
+
+    fn adder(&self, combine: Combine) -> Box<SymbolTransform> {
+        let base = self.checkerboard.base();
+        Box::new(KeystreamState::new(&*self.keystream, base, combine, "VIC"))
+    }
+}
+
+impl Cipher for Vic {
+    fn encrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        let digits = self.checkerboard.to_digits(message);
+        super::transform_all(self.adder(Box::new(vigenere::add)), &digits)
+    }
+
+    fn decrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        let digits = try!(super::transform_all(self.adder(Box::new(vigenere::subtract)), message));
+        self.checkerboard.from_digits(&digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::keystream::ChainAddition;
+    use super::super::super::{alpha, alpha_space, digits, EncodeNum, Encoding, Error};
+
+    /// The alphabet of the published board, with its two extra
+    /// symbols in their places.
+    fn wikipedia() -> Encoding {
+        let mut e = Encoding::new();
+        for c in "etaonrisbcdfghjklmpq/uvwxyz.".chars() {
+            e.insert_char(c);
+        }
+        e
+    }
+
+    #[test]
+    fn checkerboard_vector() {
+        let (e, d) = (wikipedia(), digits());
+        let board = StraddlingCheckerboard::new(&e, "", &d, "26").unwrap();
+        let message = e.vectorize_string("attackatdawn").unwrap();
+        let expected = d.vectorize_string("3113212731223655").unwrap();
+        assert_eq!(board.encrypt(&e, &message).unwrap(), expected);
+        assert_eq!(board.decrypt(&d, &expected).unwrap(), message);
+    }
+
+    #[test]
+    fn keyed_checkerboard_roundtrip() {
+        let (e, d) = (alpha(), digits());
+        let board = StraddlingCheckerboard::new(&e, "estonia", &d, "37").unwrap();
+        let message = e.vectorize_string("thequickbrownfoxjumpsoverthelazydog").unwrap();
+        let digits = board.to_digits(&message);
+        assert_eq!(board.from_digits(&digits).unwrap(), message);
+    }
+
+    #[test]
+    fn reject_empty_cell() {
+        let (e, d) = (alpha(), digits());
+        let board = StraddlingCheckerboard::new(&e, "", &d, "26").unwrap();
+        match board.from_digits(&d.vectorize_string("69").unwrap()) {
+            Err(Error::NotACode(ref code)) if code.len() == 2 => (),
+            Ok(_) => panic!("We read a letter from an empty cell!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+        match board.from_digits(&d.vectorize_string("2").unwrap()) {
+            Err(Error::NotACode(ref code)) if code.len() == 1 => (),
+            Ok(_) => panic!("We read a letter from half a code!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn reject_alphabet_too_large() {
+        match StraddlingCheckerboard::new(&alpha_space(), "", &digits(), "2") {
+            Err(Error::TooManySymbols(27, 19)) => (),
+            Ok(_) => panic!("We fit 27 letters in 19 cells!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn vic_adds_chain() {
+        let (e, d) = (wikipedia(), digits());
+        let board = StraddlingCheckerboard::new(&e, "", &d, "26").unwrap();
+        let seed: Vec<EncodeNum> = d.vectorize_string("77651").unwrap();
+        let vic = Vic::new(board, Box::new(ChainAddition::new(seed, 10).unwrap()));
+        let message = e.vectorize_string("attackatdawn").unwrap();
+        // 3113212731223655 plus 7765143165747121, without carrying
+        let expected = d.vectorize_string("0878355896960776").unwrap();
+        assert_eq!(vic.encrypt(&e, &message).unwrap(), expected);
+        assert_eq!(vic.decrypt(&d, &expected).unwrap(), message);
+    }
+}
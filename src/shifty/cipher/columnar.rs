@@ -0,0 +1,173 @@
+//! Columnar transposition, where the message is written into rows
+//! under a keyword and read back out a column at a time, taking the
+//! columns in the order of the keyword's letters.
+
+use super::Cipher;
+use super::super::{EncodeNum, Encoding, Result};
+
+#[derive(Debug, Clone)]
+pub struct Columnar {
+    /// Columns in the order they are read out
+    order: Vec<usize>,
+    /// Symbols to fill out the last row with, used in turn
+    padding: Vec<EncodeNum>,
+}
+
+/// Columns in the order their keyword symbols come in the encoding.
+/// Repeated symbols are taken from left to right.
+fn column_order(keyword: &[EncodeNum]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..keyword.len()).collect();
+    order.sort_by_key(|i| keyword[*i]);
+    order
+}
+
+impl Columnar {
+    /// With `padding` the last row is filled out to make a complete
+    /// rectangle. Without it the last row is left short, and the columns
+    /// under it are one symbol shorter than the rest.
+    pub fn new(encoding: &Encoding, keyword: &str, padding: &str) -> Result<Columnar> {
+        let keyword = try!(encoding.vectorize_string(keyword));
+        try!(super::require_key(&keyword));
+        Ok(Columnar {
+            order: column_order(&keyword),
+            padding: try!(encoding.vectorize_string(padding)),
+        })
+    }
+
+    fn width(&self) -> usize {
+        self.order.len()
+    }
+
+    fn pad(&self, message: &[EncodeNum]) -> Vec<EncodeNum> {
+        let mut padded = message.to_vec();
+        if !self.padding.is_empty() {
+            let mut filler = self.padding.iter().cycle();
+            while padded.len() % self.width() != 0 {
+                padded.push(*filler.next().unwrap());
+            }
+        }
+        padded
+    }
+
+    fn transpose(&self, message: &[EncodeNum]) -> Vec<EncodeNum> {
+        let width = self.width();
+        self.order
+            .iter()
+            .flat_map(|column| {
+                (0..).map(move |row| row * width + column).take_while(|i| *i < message.len())
+            })
+            .map(|i| message[i])
+            .collect()
+    }
+
+    fn untranspose(&self, message: &[EncodeNum]) -> Vec<EncodeNum> {
+        let width = self.width();
+        let rows = message.len() / width;
+        let long_columns = message.len() % width;
+        let mut plaintext = vec![EncodeNum::from(0); message.len()];
+        let mut read = message.iter();
+        for column in &self.order {
+            let height = if *column < long_columns { rows + 1 } else { rows };
+            for row in 0..height {
+                plaintext[row * width + column] = *read.next().unwrap();
+            }
+        }
+        plaintext
+    }
+}
+
+impl Cipher for Columnar {
+    fn encrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(self.transpose(&self.pad(message)))
+    }
+
+    fn decrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(self.untranspose(message))
+    }
+}
+
+/// Two columnar transpositions in a row, each under its own keyword.
+///
+/// Only the first one pads, so that the second one knows exactly where
+/// its columns end when decrypting.
+#[derive(Debug, Clone)]
+pub struct DoubleColumnar {
+    first: Columnar,
+    second: Columnar,
+}
+
+impl DoubleColumnar {
+    pub fn new(encoding: &Encoding,
+               first_keyword: &str,
+               second_keyword: &str,
+               padding: &str)
+               -> Result<DoubleColumnar> {
+        Ok(DoubleColumnar {
+            first: try!(Columnar::new(encoding, first_keyword, padding)),
+            second: try!(Columnar::new(encoding, second_keyword, "")),
+        })
+    }
+}
+
+impl Cipher for DoubleColumnar {
+    fn encrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        let once = try!(self.first.encrypt(encoding, message));
+        self.second.encrypt(encoding, &once)
+    }
+
+    fn decrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        let once = try!(self.second.decrypt(encoding, message));
+        self.first.decrypt(encoding, &once)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{alpha, alphanumeric_space};
+
+    #[test]
+    fn order_ties_left_to_right() {
+        let e = alpha();
+        let keyword = e.vectorize_string("tomato").unwrap();
+        assert_eq!(column_order(&keyword), vec![3, 2, 1, 5, 0, 4]);
+    }
+
+    #[test]
+    fn incomplete_rectangle() {
+        let e = alpha();
+        let c = Columnar::new(&e, "zebras", "").unwrap();
+        harness::known_vector(&c,
+                              &e,
+                              "wearediscoveredfleeatonce",
+                              "evlnacdtesearofodeecwiree");
+    }
+
+    #[test]
+    fn complete_rectangle() {
+        let e = alpha();
+        let c = Columnar::new(&e, "zebras", "qkjeu").unwrap();
+        harness::known_vector(&c,
+                              &e,
+                              "wearediscoveredfleeatonceqkjeu",
+                              "evlneacdtkeseaqrofojdeecuwiree");
+    }
+
+    #[test]
+    fn double_transposition() {
+        let e = alpha();
+        let c = DoubleColumnar::new(&e, "zebras", "stripe", "").unwrap();
+        harness::known_vector(&c,
+                              &e,
+                              "wearediscoveredfleeatonce",
+                              "caeensoiaedrlefwedreevtoc");
+    }
+
+    #[test]
+    fn custom_alphabet() {
+        let e = alphanumeric_space();
+        let c = DoubleColumnar::new(&e, "first key", "second", "").unwrap();
+        harness::roundtrip(&c, &e, "meet me at the 4th gate at 9");
+    }
+}
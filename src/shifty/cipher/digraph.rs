@@ -0,0 +1,131 @@
+//! Preparing messages for the digraphic ciphers, which all work on
+//! pairs of symbols: Playfair, Four-square and Two-square.
+//!
+//! Letters the grid has no room for are merged by the `Encoding`
+//! itself, through its character map (the `playfair` encoding writes
+//! J as I). What is left here is splitting up doubled letters and
+//! padding out the last pair.
+
+use super::super::{EncodeNum, Encoding, Error, Result};
+
+/// How a message is split into pairs, and how the fillers that
+/// splitting adds are taken back out.
+#[derive(Debug, Clone)]
+pub struct Digraphs {
+    /// Symbols used to split doubled letters and pad the last pair,
+    /// in order of preference
+    fillers: Vec<EncodeNum>,
+    strip_fillers: bool,
+}
+
+impl Digraphs {
+    /// `fillers` is tried in order, skipping any filler that would
+    /// double up the letter it is meant to separate.
+    pub fn new(encoding: &Encoding, fillers: &str) -> Result<Digraphs> {
+        let fillers = try!(encoding.vectorize_string(fillers));
+        try!(super::require_key(&fillers));
+        Ok(Digraphs {
+            fillers: fillers,
+            strip_fillers: false,
+        })
+    }
+
+    /// Have decryption guess which fillers were added and take them out:
+    /// a filler between two copies of the same letter, and a filler
+    /// finishing off the last pair.
+    pub fn stripping_fillers(mut self) -> Digraphs {
+        self.strip_fillers = true;
+        self
+    }
+
+    fn filler_for(&self, symbol: EncodeNum) -> EncodeNum {
+        *self.fillers.iter().find(|f| **f != symbol).unwrap_or(&self.fillers[0])
+    }
+
+    /// Split `message` into pairs, never putting a letter with itself.
+    pub fn split(&self, message: &[EncodeNum]) -> Vec<(EncodeNum, EncodeNum)> {
+        let mut pairs = Vec::with_capacity(message.len() / 2 + 1);
+        let mut i = 0;
+        while i < message.len() {
+            let a = message[i];
+            match message.get(i + 1) {
+                Some(&b) if b != a => {
+                    pairs.push((a, b));
+                    i += 2;
+                }
+                _ => {
+                    pairs.push((a, self.filler_for(a)));
+                    i += 1;
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Split the message into pairs and put each one through `pair`.
+    pub fn encrypt<F>(&self, message: &[EncodeNum], pair: F) -> Vec<EncodeNum>
+        where F: Fn(EncodeNum, EncodeNum) -> [EncodeNum; 2]
+    {
+        self.split(message)
+            .into_iter()
+            .flat_map(|(a, b)| pair(a, b).to_vec())
+            .collect()
+    }
+
+    /// Put each pair of ciphertext through `pair`, then take out the
+    /// fillers if asked to.
+    pub fn decrypt<F>(&self, message: &[EncodeNum], pair: F) -> Result<Vec<EncodeNum>>
+        where F: Fn(EncodeNum, EncodeNum) -> [EncodeNum; 2]
+    {
+        if message.len() % 2 != 0 {
+            return Err(Error::NotWholeBlocks(message.len(), 2));
+        }
+        let plaintext: Vec<EncodeNum> = message.chunks(2)
+            .flat_map(|p| pair(p[0], p[1]).to_vec())
+            .collect();
+        if self.strip_fillers {
+            Ok(self.strip(plaintext))
+        } else {
+            Ok(plaintext)
+        }
+    }
+
+    fn strip(&self, message: Vec<EncodeNum>) -> Vec<EncodeNum> {
+        let last = message.len().saturating_sub(1);
+        message.iter()
+            .enumerate()
+            .filter(|&(i, m)| {
+                let added = i % 2 == 1 && self.fillers.contains(m) &&
+                            (i == last || message[i - 1] == message[i + 1]);
+                !added
+            })
+            .map(|(_, m)| *m)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::{playfair, EncodeNum};
+
+    #[test]
+    fn pad_with_next_filler() {
+        let e = playfair();
+        let d = Digraphs::new(&e, "xq").unwrap();
+        let pairs = d.split(&e.vectorize_string("xxx").unwrap());
+        let expected = e.vectorize_string("xqxqxq").unwrap();
+        let flat: Vec<EncodeNum> = pairs.iter().flat_map(|&(a, b)| vec![a, b]).collect();
+        assert_eq!(flat, expected);
+    }
+
+    #[test]
+    fn strip_added_fillers() {
+        let e = playfair();
+        let d = Digraphs::new(&e, "x").unwrap().stripping_fillers();
+        let message = e.vectorize_string("balloons").unwrap();
+        let padded = d.encrypt(&message, |a, b| [a, b]);
+        assert_eq!(padded, e.vectorize_string("balxloonsx").unwrap());
+        assert_eq!(d.decrypt(&padded, |a, b| [a, b]).unwrap(), message);
+    }
+}
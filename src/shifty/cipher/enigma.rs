@@ -0,0 +1,504 @@
+//! The Enigma machines of the German army and navy: the three rotor
+//! Enigma I and M3, and the four rotor naval M4.
+//!
+//! A letter goes through the plugboard, the rotors from right to left,
+//! the reflector, the rotors back from left to right, and the plugboard
+//! again. The reflector pairs up every letter with another, so the
+//! machine undoes itself: decrypting is encrypting from the same start.
+//!
+//! The historical wirings are written in capitals and read through the
+//! character map of the `Encoding`, or as the other case of a letter the
+//! encoding lacks, so they need an encoding of the 26 letters such as
+//! `alpha` or a plain lowercase alphabet. Rotors given their own wiring
+//! can work on an alphabet of any size.
+
+use super::{Cipher, SymbolTransform};
+use super::substitution::Substitution;
+use super::super::{Action, EncodeNum, Encoding, Error, Result};
+use super::super::error::ConfigError;
+use super::super::parser::{self, EnigmaConfig, RotorSpec, WheelSetting};
+
+/// Name, wiring and turnover letters of each historical rotor. Beta
+/// and Gamma only went in the fourth place of an M4, and never turn.
+const ROTORS: [(&'static str, &'static str, &'static str); 10] =
+    [("I", "EKMFLGDQVZNTOWYHXUSPAIBRCJ", "Q"),
+     ("II", "AJDKSIRUXBLHWTMCQGZNPYFVOE", "E"),
+     ("III", "BDFHJLCPRTXVZNYEIWGAKMUSQO", "V"),
+     ("IV", "ESOVPZJAYQUIRHXLNFTGKDCMWB", "J"),
+     ("V", "VZBRGITYUPSDNHLXAWMJQOFECK", "Z"),
+     ("VI", "JPGVOUMFYQBENHZRDKASXLICTW", "ZM"),
+     ("VII", "NZJHGRCXMYSWBOUFAIVLPEKQDT", "ZM"),
+     ("VIII", "FKQHTLXOCBJSPDZRAMEWNIUYGV", "ZM"),
+     ("Beta", "LEYJVCNIXWPBQMDRTAKZGFUHOS", ""),
+     ("Gamma", "FSOKANUERHMBTIYCWLQPZXVGJD", "")];
+
+/// Name and wiring of each historical reflector. The thin ones made
+/// room for the fourth rotor of an M4.
+const REFLECTORS: [(&'static str, &'static str); 5] =
+    [("A", "EJMZALYXVBWFCRQUONTSPIKHGD"),
+     ("B", "YRUHQSLDPXNGOKMIEBFZCWVJAT"),
+     ("C", "FVPJIAOYEDRZXWGCTKUQSBNMHL"),
+     ("B-thin", "ENKQAUYWJICOPBLMDXZVFTHRGS"),
+     ("C-thin", "RDOBJNTKVEHMLFCWZAXGYIPSUQ")];
+
+/// Letters of a wiring or setting, taking the other case of any letter
+/// the encoding has no character for.
+fn vectorize(encoding: &Encoding, s: &str) -> Result<Vec<EncodeNum>> {
+    encoding.map_string(s)
+        .chars()
+        .map(|c| {
+            encoding.char_to_number(&c).or_else(|err| {
+                let other = if c.is_ascii_uppercase() {
+                    c.to_ascii_lowercase()
+                } else {
+                    c.to_ascii_uppercase()
+                };
+                encoding.char_to_number(&other).map_err(|_| err)
+            })
+        })
+        .collect()
+}
+
+fn index(n: EncodeNum) -> usize {
+    u64::from(n) as usize
+}
+
+#[derive(Debug, Clone)]
+struct Rotor {
+    wiring: Substitution,
+    /// Positions at which this rotor turns the one to its left
+    notches: Vec<usize>,
+    /// How far the wiring is turned against the letters on the ring
+    ring: usize,
+}
+
+impl Rotor {
+    fn new(encoding: &Encoding, spec: &RotorSpec) -> Result<Rotor> {
+        let (wiring, notches) = match *spec {
+            RotorSpec::Named(ref name) => {
+                match ROTORS.iter().find(|r| r.0.eq_ignore_ascii_case(name)) {
+                    Some(&(_, wiring, notches)) => (wiring, notches),
+                    None => {
+                        return Err(From::from(ConfigError::SchemaError(format!("No rotor is \
+                                                                                named {:?}",
+                                                                               name))))
+                    }
+                }
+            }
+            RotorSpec::Wired { ref wiring, ref notches } => (&wiring[..], &notches[..]),
+        };
+        Ok(Rotor {
+            wiring: try!(Substitution::new(encoding, try!(vectorize(encoding, wiring)))),
+            notches: try!(vectorize(encoding, notches)).into_iter().map(index).collect(),
+            ring: 0,
+        })
+    }
+
+    /// Send `symbol` through the rotor turned to `position`, towards the
+    /// reflector when encrypting and back from it when decrypting.
+    fn transform(&self, symbol: usize, position: usize, size: usize, action: Action) -> usize {
+        let shift = (position + size - self.ring) % size;
+        let contact = EncodeNum::from(((symbol + shift) % size) as u64);
+        (index(self.wiring.transform_num(contact, action)) + size - shift) % size
+    }
+}
+
+/// An Enigma set up with its rotors, ring settings, start positions,
+/// reflector and plugboard.
+#[derive(Debug, Clone)]
+pub struct Enigma {
+    /// From left to right, as they sit in the machine
+    rotors: Vec<Rotor>,
+    reflector: Substitution,
+    plugboard: Substitution,
+    /// The letter each rotor shows in its window before the first key press
+    positions: Vec<usize>,
+    size: usize,
+}
+
+impl Enigma {
+    /// Set up a machine from the `[enigma]` table of a config. Rings and
+    /// start positions left out are all at the first letter, and a
+    /// missing plugboard has no cables.
+    pub fn new(encoding: &Encoding, config: &EnigmaConfig) -> Result<Enigma> {
+        let size = encoding.size();
+        if size == 0 {
+            return Err(Error::EmptyEncoding);
+        }
+        let mut rotors: Vec<Rotor> = try!(config.rotors
+            .iter()
+            .map(|spec| Rotor::new(encoding, spec))
+            .collect());
+        let rings = try!(Enigma::wheel_setting(encoding, &config.rings, rotors.len()));
+        for (rotor, ring) in rotors.iter_mut().zip(rings) {
+            rotor.ring = ring;
+        }
+        let positions = try!(Enigma::wheel_setting(encoding, &config.positions, rotors.len()));
+
+        let reflector = match REFLECTORS.iter()
+            .find(|r| r.0.eq_ignore_ascii_case(&config.reflector)) {
+            Some(&(_, wiring)) => wiring,
+            None => &config.reflector[..],
+        };
+        let reflector = try!(Substitution::new(encoding, try!(vectorize(encoding, reflector))));
+        for n in 0..size as u64 {
+            let symbol = EncodeNum::from(n);
+            let reflected = reflector.transform_num(symbol, Action::Encrypt);
+            let back = reflector.transform_num(reflected, Action::Encrypt);
+            if reflected == symbol || back != symbol {
+                return Err(Error::NotAReflector(try!(encoding.number_to_char(&symbol))));
+            }
+        }
+
+        let plugboard = try!(Enigma::plugboard(encoding,
+                                               config.plugboard.as_ref().map_or("", |p| &p[..])));
+        Ok(Enigma {
+            rotors: rotors,
+            reflector: reflector,
+            plugboard: plugboard,
+            positions: positions,
+            size: size,
+        })
+    }
+
+    /// Read the machine from the `[enigma]` table of a TOML config.
+    pub fn parse(encoding: &Encoding, toml: &str) -> Result<Enigma> {
+        let root_table = try!(parser::read_toml_string(toml));
+        match try!(parser::parse_enigma(&root_table)) {
+            Some(config) => Enigma::new(encoding, &config),
+            None => {
+                Err(From::from(ConfigError::SchemaError("Config has no [enigma] table"
+                    .to_string())))
+            }
+        }
+    }
+
+    /// Start from the letters in `positions` instead, one for each rotor,
+    /// as the operator would for each new message.
+    pub fn with_positions(mut self, encoding: &Encoding, positions: &str) -> Result<Enigma> {
+        let setting = Some(WheelSetting::Letters(positions.to_string()));
+        self.positions = try!(Enigma::wheel_setting(encoding, &setting, self.rotors.len()));
+        Ok(self)
+    }
+
+    fn wheel_setting(encoding: &Encoding,
+                     setting: &Option<WheelSetting>,
+                     rotors: usize)
+                     -> Result<Vec<usize>> {
+        let wheels: Vec<usize> = match *setting {
+            Some(WheelSetting::Letters(ref letters)) => {
+                try!(vectorize(encoding, letters)).into_iter().map(index).collect()
+            }
+            Some(WheelSetting::Numbers(ref numbers)) => {
+                let mut wheels = Vec::with_capacity(numbers.len());
+                for n in numbers {
+                    if *n == 0 {
+                        return Err(From::from(ConfigError::SchemaError(format!("Enigma \
+                                                                                settings count \
+                                                                                from 1: {:?}",
+                                                                               numbers))));
+                    }
+                    if *n > encoding.size() as u64 {
+                        return Err(Error::NumberNotInEncoding(EncodeNum::from(n - 1)));
+                    }
+                    wheels.push(*n as usize - 1);
+                }
+                wheels
+            }
+            None => vec![0; rotors],
+        };
+        if wheels.len() != rotors {
+            return Err(From::from(ConfigError::SchemaError(format!("Enigma has {} rotors \
+                                                                    but {} settings: {:?}",
+                                                                   rotors,
+                                                                   wheels.len(),
+                                                                   setting))));
+        }
+        Ok(wheels)
+    }
+
+    /// Cables swapping the pairs of letters in `plugs`, such as "AV BS CG".
+    fn plugboard(encoding: &Encoding, plugs: &str) -> Result<Substitution> {
+        let size = encoding.size();
+        let mut swaps: Vec<EncodeNum> = (0..size as u64).map(EncodeNum::from).collect();
+        let mut used = vec![false; size];
+        for pair in plugs.split_whitespace() {
+            let ends = try!(vectorize(encoding, pair));
+            if ends.len() != 2 || ends[0] == ends[1] || used[index(ends[0])] ||
+               used[index(ends[1])] {
+                return Err(Error::InvalidPlug(pair.to_string()));
+            }
+            for &(a, b) in &[(ends[0], ends[1]), (ends[1], ends[0])] {
+                swaps[index(a)] = b;
+                used[index(a)] = true;
+            }
+        }
+        Substitution::new(encoding, swaps)
+    }
+
+    /// Turn the rotors for the next key press. The rightmost rotor always
+    /// turns; a rotor at its notch turns the one to its left, and the
+    /// middle rotor turns itself along with it, the double step. A fourth
+    /// rotor never turns.
+    fn step(&self, positions: &mut [usize]) {
+        let n = positions.len();
+        let at_notch = |i: usize, positions: &[usize]| {
+            self.rotors[i].notches.contains(&positions[i])
+        };
+        let mut turn = vec![false; n];
+        if n >= 1 {
+            turn[n - 1] = true;
+        }
+        if n >= 2 && at_notch(n - 1, positions) {
+            turn[n - 2] = true;
+        }
+        if n >= 3 && at_notch(n - 2, positions) {
+            turn[n - 2] = true;
+            turn[n - 3] = true;
+        }
+        for (position, turn) in positions.iter_mut().zip(turn) {
+            if turn {
+                *position = (*position + 1) % self.size;
+            }
+        }
+    }
+
+    fn press(&self, symbol: EncodeNum, positions: &mut [usize]) -> EncodeNum {
+        self.step(positions);
+        let mut c = index(self.plugboard.transform_num(symbol, Action::Encrypt));
+        for (rotor, position) in self.rotors.iter().zip(positions.iter()).rev() {
+            c = rotor.transform(c, *position, self.size, Action::Encrypt);
+        }
+        c = index(self.reflector.transform_num(EncodeNum::from(c as u64), Action::Encrypt));
+        for (rotor, position) in self.rotors.iter().zip(positions.iter()) {
+            c = rotor.transform(c, *position, self.size, Action::Decrypt);
+        }
+        self.plugboard.transform_num(EncodeNum::from(c as u64), Action::Encrypt)
+    }
+}
+
+/// Symbols passed through without being typed only turn the rotors
+/// when the key is asked to move on past them.
+struct EnigmaState {
+    enigma: Enigma,
+    positions: Vec<usize>,
+}
+
+impl SymbolTransform for EnigmaState {
+    fn transform(&mut self, symbol: EncodeNum) -> Result<EncodeNum> {
+        Ok(self.enigma.press(symbol, &mut self.positions))
+    }
+
+    fn skip(&mut self) -> Result<()> {
+        self.enigma.step(&mut self.positions);
+        Ok(())
+    }
+}
+
+impl Cipher for Enigma {
+    fn encrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        super::transform_all(self.symbolwise(encoding, Action::Encrypt).unwrap(), message)
+    }
+
+    fn decrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        super::transform_all(self.symbolwise(encoding, Action::Decrypt).unwrap(), message)
+    }
+
+    fn symbolwise(&self, _encoding: &Encoding, _action: Action) -> Option<Box<SymbolTransform>> {
+        Some(Box::new(EnigmaState {
+            enigma: self.clone(),
+            positions: self.positions.clone(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{alpha, Action, Encoding, Error};
+    use super::super::super::parser::{EnigmaConfig, RotorSpec, WheelSetting};
+
+    fn named(rotors: &[&str]) -> Vec<RotorSpec> {
+        rotors.iter().map(|r| RotorSpec::Named(r.to_string())).collect()
+    }
+
+    fn enigma_i() -> EnigmaConfig {
+        EnigmaConfig {
+            rotors: named(&["I", "II", "III"]),
+            reflector: "B".to_string(),
+            rings: None,
+            positions: None,
+            plugboard: None,
+        }
+    }
+
+    /// Rotors I II III, reflector B, every ring and position at A.
+    #[test]
+    fn enigma_i_vector() {
+        let e = alpha();
+        let enigma = Enigma::new(&e, &enigma_i()).unwrap();
+        harness::known_vector(&enigma, &e, "aaaaa", "bdzgo");
+    }
+
+    #[test]
+    fn middle_rotor_double_steps() {
+        let e = alpha();
+        let enigma = Enigma::new(&e, &enigma_i()).unwrap();
+        let mut positions = vec![0, 3, 20];
+        let mut windows = Vec::new();
+        for _ in 0..3 {
+            enigma.step(&mut positions);
+            windows.push(positions.iter().map(|p| (b'A' + *p as u8) as char).collect::<String>());
+        }
+        assert_eq!(windows, vec!["ADV", "AEW", "BFX"]);
+    }
+
+    /// Operation Barbarossa, 1941: an M3 message from its first part.
+    #[test]
+    fn m3_vector() {
+        let e = alpha();
+        let config = EnigmaConfig {
+            rotors: named(&["II", "IV", "V"]),
+            reflector: "B".to_string(),
+            rings: Some(WheelSetting::Numbers(vec![2, 21, 12])),
+            positions: Some(WheelSetting::Letters("BLA".to_string())),
+            plugboard: Some("AV BS CG DL FU HZ IN KM OW RX".to_string()),
+        };
+        let enigma = Enigma::new(&e, &config).unwrap();
+        harness::known_vector(&enigma,
+                              &e,
+                              "aufklxabteilungxvonxkurtinowaxkurtinowaxnordwestlx",
+                              "edpudnrgyszrcxnuytpomrmbofktbzrezkmlxlvefgueysiozv");
+    }
+
+    /// Sent to U-534 in 1945, on an M4 with the Beta rotor and thin
+    /// reflector B, read from a TOML config.
+    #[test]
+    fn m4_vector_from_toml() {
+        let e = alpha();
+        let toml = r#"
+        [enigma]
+        rotors = ["Beta", "II", "IV", "I"]
+        reflector = "B-thin"
+        rings = "AAAV"
+        positions = "VJNA"
+        plugboard = "AT BL DF GJ HM NW OP QY RZ VX"
+        "#;
+        let enigma = Enigma::parse(&e, toml).unwrap();
+        harness::known_vector(&enigma,
+                              &e,
+                              "vonvonjlooksjhffttteinseinsdreizwoyyqnnsneuninhaltxx",
+                              "nczwvusxpnyminhzxmqxsfwxwlkjahshnmcoccakuqpmkcsmhkse");
+    }
+
+    #[test]
+    fn lowercase_alphabet_from_toml() {
+        let e = Encoding::parse(r#"
+        alphabet = ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m",
+                    "n", "o", "p", "q", "r", "s", "t", "u", "v", "w", "x", "y", "z"]
+        "#)
+            .unwrap();
+        let enigma = Enigma::new(&e, &enigma_i()).unwrap();
+        harness::known_vector(&enigma, &e, "aaaaa", "bdzgo");
+    }
+
+    #[test]
+    fn skip_turns_the_rotors() {
+        let e = alpha();
+        let enigma = Enigma::new(&e, &enigma_i()).unwrap();
+        let mut state = enigma.symbolwise(&e, Action::Encrypt).unwrap();
+        state.skip().unwrap();
+        let a = e.char_to_number(&'a').unwrap();
+        assert_eq!(state.transform(a).unwrap(), e.char_to_number(&'d').unwrap());
+    }
+
+    #[test]
+    fn custom_wiring_roundtrip() {
+        let mut e = Encoding::new();
+        for c in "abcdef".chars() {
+            e.insert_char(c);
+        }
+        let config = EnigmaConfig {
+            rotors: vec![RotorSpec::Wired {
+                             wiring: "cafebd".to_string(),
+                             notches: "f".to_string(),
+                         },
+                         RotorSpec::Wired {
+                             wiring: "fedcba".to_string(),
+                             notches: "a".to_string(),
+                         }],
+            reflector: "badcfe".to_string(),
+            rings: Some(WheelSetting::Letters("bc".to_string())),
+            positions: None,
+            plugboard: Some("ae".to_string()),
+        };
+        let enigma = Enigma::new(&e, &config).unwrap();
+        harness::roundtrip(&enigma, &e, "facadebeadbeefdecade");
+    }
+
+    #[test]
+    fn override_start_positions() {
+        let e = alpha();
+        let enigma = Enigma::new(&e, &enigma_i()).unwrap().with_positions(&e, "aaa").unwrap();
+        harness::known_vector(&enigma, &e, "aaaaa", "bdzgo");
+        match Enigma::new(&e, &enigma_i()).unwrap().with_positions(&e, "aa") {
+            Err(Error::InvalidConfig(_)) => (),
+            Ok(_) => panic!("We set three rotors from two letters!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn reject_reflector_with_fixed_point() {
+        let mut config = enigma_i();
+        config.reflector = "AJMZELYXVBWFCRQUONTSPIKHGD".to_string();
+        match Enigma::new(&alpha(), &config) {
+            Err(Error::NotAReflector('a')) => (),
+            Ok(_) => panic!("We built a reflector that leaves A alone!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn reject_reused_plug() {
+        let mut config = enigma_i();
+        config.plugboard = Some("AB CA".to_string());
+        match Enigma::new(&alpha(), &config) {
+            Err(Error::InvalidPlug(ref pair)) if pair == "CA" => (),
+            Ok(_) => panic!("We plugged two cables into A!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn reject_ring_setting_of_zero() {
+        let mut config = enigma_i();
+        config.rings = Some(WheelSetting::Numbers(vec![1, 0, 1]));
+        match Enigma::new(&alpha(), &config) {
+            Err(Error::InvalidConfig(_)) => (),
+            Ok(_) => panic!("We set a ring before its first letter!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn reject_empty_encoding() {
+        match Enigma::new(&Encoding::new(), &enigma_i()) {
+            Err(Error::EmptyEncoding) => (),
+            Ok(_) => panic!("We built an Enigma with no letters!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn reject_unknown_rotor() {
+        let mut config = enigma_i();
+        config.rotors = named(&["I", "II", "IX"]);
+        match Enigma::new(&alpha(), &config) {
+            Err(Error::InvalidConfig(_)) => (),
+            Ok(_) => panic!("We found a ninth rotor!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
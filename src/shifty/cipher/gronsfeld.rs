@@ -0,0 +1,76 @@
+//! The Gronsfeld cipher, Vigenère with a key of digits that are
+//! the shifts themselves rather than characters of the alphabet.
+
+use super::{Cipher, SymbolTransform, Vigenere};
+use super::super::{Action, EncodeNum, Encoding, Error, Result};
+
+#[derive(Debug)]
+pub struct Gronsfeld {
+    vigenere: Vigenere,
+}
+
+impl Gronsfeld {
+    /// Every character of `digits` has to be a decimal digit smaller
+    /// than the size of the alphabet, so that each shift is distinct.
+    pub fn from_digits(encoding: &Encoding, digits: &str) -> Result<Gronsfeld> {
+        let size = encoding.size();
+        let mut key = Vec::with_capacity(digits.len());
+        for c in digits.chars() {
+            match c.to_digit(10) {
+                Some(d) if (d as usize) < size => key.push(EncodeNum::from(d as u64)),
+                _ => return Err(Error::InvalidDigit(c, size)),
+            }
+        }
+        Ok(Gronsfeld { vigenere: try!(Vigenere::new(key)) })
+    }
+}
+
+impl Cipher for Gronsfeld {
+    fn encrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        self.vigenere.encrypt(encoding, message)
+    }
+
+    fn decrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        self.vigenere.decrypt(encoding, message)
+    }
+
+    fn symbolwise(&self, encoding: &Encoding, action: Action) -> Option<Box<SymbolTransform>> {
+        self.vigenere.symbolwise(encoding, action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{alpha, Encoding, Error};
+
+    #[test]
+    fn gronsfeld_vector() {
+        let e = alpha();
+        let g = Gronsfeld::from_digits(&e, "31415").unwrap();
+        harness::known_vector(&g, &e, "attackatdawn", "duxbhnbxefzo");
+    }
+
+    #[test]
+    fn reject_non_digit() {
+        match Gronsfeld::from_digits(&alpha(), "31a15") {
+            Err(Error::InvalidDigit('a', 26)) => (),
+            Ok(_) => panic!("We accepted a letter as a shift!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn reject_shift_past_alphabet() {
+        let mut e = Encoding::new();
+        for c in "abcd".chars() {
+            e.insert_char(c);
+        }
+        match Gronsfeld::from_digits(&e, "314") {
+            Err(Error::InvalidDigit('4', 4)) => (),
+            Ok(_) => panic!("We accepted a shift larger than the alphabet!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! The Hill cipher, which multiplies blocks of the message by a
+//! square matrix modulo the size of the alphabet.
+
+use super::Cipher;
+use super::super::{EncodeNum, Encoding, Error, Result};
+use super::super::error::ConfigError;
+use super::super::modular::{self, Matrix};
+use super::super::parser::{self, HillKey};
+
+#[derive(Debug, Clone)]
+pub struct Hill {
+    key: Matrix,
+    inverse: Matrix,
+    /// Symbols to fill out the last block with, used in turn
+    padding: Vec<EncodeNum>,
+}
+
+impl Hill {
+    /// Each block of plaintext, as a column, is multiplied by `key`.
+    /// The determinant of `key` needs an inverse modulo the alphabet size,
+    /// or the ciphertext could not be decrypted.
+    ///
+    /// Messages are padded out to whole blocks with `padding`. Without any
+    /// padding, messages have to come in whole blocks.
+    pub fn new(encoding: &Encoding, key: Matrix, padding: &str) -> Result<Hill> {
+        let size = encoding.size();
+        let modulus = size as u64;
+        if size == 0 {
+            return Err(Error::EmptyEncoding);
+        }
+        if key.is_empty() || key.iter().any(|row| row.len() != key.len()) {
+            return Err(From::from(ConfigError::SchemaError(format!("Hill key is not a \
+                                                                    square matrix: {:?}",
+                                                                   key))));
+        }
+        let key: Matrix = key.iter()
+            .map(|row| row.iter().map(|x| x % modulus).collect())
+            .collect();
+        let inverse = match modular::inverse_matrix(&key, modulus) {
+            Some(inverse) => inverse,
+            None => return Err(Error::SingularMatrix(modular::determinant(&key, modulus), size)),
+        };
+        Ok(Hill {
+            key: key,
+            inverse: inverse,
+            padding: try!(encoding.vectorize_string(padding)),
+        })
+    }
+
+    /// Fill the matrix row by row from `keytext`, which needs a square
+    /// number of characters: "gybnqkurp" makes a 3x3 matrix.
+    pub fn from_key(encoding: &Encoding, keytext: &str, padding: &str) -> Result<Hill> {
+        let numbers = try!(encoding.vectorize_string(keytext));
+        try!(super::require_key(&numbers));
+        let side = try!(super::grid_side(numbers.len()));
+        let key = numbers.chunks(side)
+            .map(|row| row.iter().map(|n| u64::from(*n)).collect())
+            .collect();
+        Hill::new(encoding, key, padding)
+    }
+
+    /// Read the key from the `[hill]` table of a TOML config, which holds
+    /// either a `matrix` or a `key`, and maybe the `padding` to use instead
+    /// of `default_padding`.
+    pub fn parse(encoding: &Encoding, toml: &str, default_padding: &str) -> Result<Hill> {
+        let root_table = try!(parser::read_toml_string(toml));
+        let config = match try!(parser::parse_hill(&root_table)) {
+            Some(config) => config,
+            None => {
+                return Err(From::from(ConfigError::SchemaError("Config has no [hill] table"
+                    .to_string())))
+            }
+        };
+        let padding = config.padding.as_ref().map(|p| &p[..]).unwrap_or(default_padding);
+        match config.key {
+            HillKey::Matrix(key) => Hill::new(encoding, key, padding),
+            HillKey::Text(text) => Hill::from_key(encoding, &text, padding),
+        }
+    }
+
+    pub fn key(&self) -> &Matrix {
+        &self.key
+    }
+
+    fn block_size(&self) -> usize {
+        self.key.len()
+    }
+
+    fn apply(&self, matrix: &Matrix, encoding: &Encoding, message: &[EncodeNum]) -> Vec<EncodeNum> {
+        let modulus = encoding.size() as u64;
+        message.chunks(self.block_size())
+            .flat_map(|block| {
+                let column: Vec<u64> = block.iter().map(|n| u64::from(*n)).collect();
+                modular::multiply(matrix, &column, modulus)
+            })
+            .map(EncodeNum::from)
+            .collect()
+    }
+}
+
+impl Cipher for Hill {
+    fn encrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        let block = self.block_size();
+        let mut padded = message.to_vec();
+        if padded.len() % block != 0 {
+            if self.padding.is_empty() {
+                return Err(Error::NotWholeBlocks(message.len(), block));
+            }
+            let mut filler = self.padding.iter().cycle();
+            while padded.len() % block != 0 {
+                padded.push(*filler.next().unwrap());
+            }
+        }
+        Ok(self.apply(&self.key, encoding, &padded))
+    }
+
+    fn decrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        if message.len() % self.block_size() != 0 {
+            return Err(Error::NotWholeBlocks(message.len(), self.block_size()));
+        }
+        Ok(self.apply(&self.inverse, encoding, message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{alpha, alphanumeric_space, Encoding, Error};
+
+    #[test]
+    fn wikipedia_vectors() {
+        let e = alpha();
+        let h = Hill::from_key(&e, "gybnqkurp", "x").unwrap();
+        harness::known_vector(&h, &e, "act", "poh");
+        harness::known_vector(&h, &e, "cat", "fin");
+    }
+
+    #[test]
+    fn two_by_two() {
+        let e = alpha();
+        let h = Hill::new(&e, vec![vec![3, 3], vec![2, 5]], "x").unwrap();
+        harness::known_vector(&h, &e, "helpme", "hiatws");
+    }
+
+    #[test]
+    fn pad_last_block() {
+        let e = alpha();
+        let h = Hill::from_key(&e, "gybnqkurp", "xy").unwrap();
+        let padded = e.vectorize_string("actcxy").unwrap();
+        let ciphertext = h.encrypt(&e, &e.vectorize_string("actc").unwrap()).unwrap();
+        assert_eq!(h.decrypt(&e, &ciphertext).unwrap(), padded);
+    }
+
+    #[test]
+    fn no_padding_needs_whole_blocks() {
+        let e = alpha();
+        let h = Hill::from_key(&e, "gybnqkurp", "").unwrap();
+        match h.encrypt(&e, &e.vectorize_string("actc").unwrap()) {
+            Err(Error::NotWholeBlocks(4, 3)) => (),
+            Ok(_) => panic!("We encrypted part of a block!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn reject_singular_key() {
+        let e = alpha();
+        match Hill::new(&e, vec![vec![2, 4], vec![1, 3]], "x") {
+            Err(Error::SingularMatrix(2, 26)) => (),
+            Ok(_) => panic!("We accepted a key that can not be inverted!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn reject_empty_encoding() {
+        match Hill::new(&Encoding::new(), vec![vec![1]], "") {
+            Err(Error::EmptyEncoding) => (),
+            Ok(_) => panic!("We built a Hill cipher with no alphabet!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn custom_alphabet() {
+        let e = alphanumeric_space();
+        let h = Hill::from_key(&e, "hill", " ").unwrap();
+        harness::roundtrip(&h, &e, "meet me at the 4th gate at 9");
+    }
+
+    #[test]
+    fn parse_from_toml() {
+        let test_string = r#"
+        alphabet = ["a", "b", "c", "d", "e"]
+        [hill]
+        matrix = [[1, 2], [3, 4]]
+        "#;
+        let e = ::shifty::Encoding::parse(test_string).unwrap();
+        let h = Hill::parse(&e, test_string, "e").unwrap();
+        harness::roundtrip(&h, &e, "abcdea");
+    }
+}
@@ -0,0 +1,296 @@
+//! Keystreams, the shift a Vigenère-style cipher uses at each position
+//! of the message.
+//!
+//! The ciphers only combine one message symbol with one key symbol, so
+//! any `Keystream` can drive any of them: a repeating keyword, the
+//! Trithemius progression, a keyword that moves on every period, or
+//! anything else that can be written as a stream of symbols.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::rc::Rc;
+use super::SymbolTransform;
+use super::super::{EncodeNum, Error, Result};
+
+/// Where a cipher gets its key symbols from.
+///
+/// Symbols may be larger than the alphabet; they are taken modulo its size.
+pub trait Keystream: fmt::Debug {
+    /// The stream from its first position. A stream that ends before the
+    /// message does leaves the rest of the message without a key.
+    fn start(&self) -> Box<Iterator<Item = EncodeNum>>;
+}
+
+/// The key over and over, as in plain Vigenère.
+#[derive(Debug, Clone)]
+pub struct Repeating {
+    key: Vec<EncodeNum>,
+}
+
+impl Repeating {
+    pub fn new(key: Vec<EncodeNum>) -> Result<Repeating> {
+        try!(super::require_key(&key));
+        Ok(Repeating { key: key })
+    }
+}
+
+impl Keystream for Repeating {
+    fn start(&self) -> Box<Iterator<Item = EncodeNum>> {
+        Box::new(self.key.clone().into_iter().cycle())
+    }
+}
+
+/// The key once through, then nothing, as in a running key.
+#[derive(Debug, Clone)]
+pub struct Finite {
+    key: Vec<EncodeNum>,
+}
+
+impl Finite {
+    pub fn new(key: Vec<EncodeNum>) -> Result<Finite> {
+        try!(super::require_key(&key));
+        Ok(Finite { key: key })
+    }
+}
+
+impl Keystream for Finite {
+    fn start(&self) -> Box<Iterator<Item = EncodeNum>> {
+        Box::new(self.key.clone().into_iter())
+    }
+}
+
+/// `start`, then `step` more at every position.
+#[derive(Debug, Clone, Copy)]
+pub struct Progressive {
+    start: u64,
+    step: u64,
+}
+
+impl Progressive {
+    pub fn new(start: u64, step: u64) -> Progressive {
+        Progressive {
+            start: start,
+            step: step,
+        }
+    }
+
+    /// Trithemius' tabula recta, read one row further down for
+    /// every letter: shifts of 0, 1, 2 and so on.
+    pub fn trithemius() -> Progressive {
+        Progressive::new(0, 1)
+    }
+}
+
+impl Keystream for Progressive {
+    fn start(&self) -> Box<Iterator<Item = EncodeNum>> {
+        let (start, step) = (self.start, self.step);
+        Box::new((0..).map(move |i: u64| EncodeNum::from(start.wrapping_add(i.wrapping_mul(step)))))
+    }
+}
+
+/// The key repeated, with `increment` added to every symbol each
+/// time it starts over.
+#[derive(Debug, Clone)]
+pub struct Incrementing {
+    key: Vec<EncodeNum>,
+    increment: u64,
+}
+
+impl Incrementing {
+    pub fn new(key: Vec<EncodeNum>, increment: u64) -> Result<Incrementing> {
+        try!(super::require_key(&key));
+        Ok(Incrementing {
+            key: key,
+            increment: increment,
+        })
+    }
+}
+
+impl Keystream for Incrementing {
+    fn start(&self) -> Box<Iterator<Item = EncodeNum>> {
+        let key = self.key.clone();
+        let increment = self.increment;
+        Box::new((0..).map(move |i: usize| {
+            let period = (i / key.len()) as u64;
+            EncodeNum::from(u64::from(key[i % key.len()])
+                .wrapping_add(period.wrapping_mul(increment)))
+        }))
+    }
+}
+
+/// Chain addition, as in the VIC cipher: the seed, then the sum of the
+/// first two digits of the last `seed.len()`, dropping the carry.
+#[derive(Debug, Clone)]
+pub struct ChainAddition {
+    seed: Vec<EncodeNum>,
+    base: u64,
+}
+
+impl ChainAddition {
+    /// Sums are taken modulo `base`, which is 10 for decimal digits.
+    pub fn new(seed: Vec<EncodeNum>, base: u64) -> Result<ChainAddition> {
+        try!(super::require_key(&seed));
+        if base == 0 {
+            return Err(Error::ZeroParameter("digits"));
+        }
+        Ok(ChainAddition {
+            seed: seed,
+            base: base,
+        })
+    }
+}
+
+struct Chain {
+    window: VecDeque<u64>,
+    base: u64,
+}
+
+impl Iterator for Chain {
+    type Item = EncodeNum;
+
+    fn next(&mut self) -> Option<EncodeNum> {
+        let first = self.window.pop_front().unwrap();
+        let second = *self.window.front().unwrap_or(&first);
+        self.window.push_back((first + second) % self.base);
+        Some(EncodeNum::from(first))
+    }
+}
+
+impl Keystream for ChainAddition {
+    fn start(&self) -> Box<Iterator<Item = EncodeNum>> {
+        let base = self.base;
+        Box::new(Chain {
+            window: self.seed.iter().map(|n| u64::from(*n) % base).collect(),
+            base: base,
+        })
+    }
+}
+
+/// Whatever a function of the position gives.
+#[allow(dead_code)]
+#[derive(Clone)]
+pub struct Generator {
+    generate: Rc<Fn(usize) -> EncodeNum>,
+}
+
+#[allow(dead_code)]
+impl Generator {
+    pub fn new<F>(generate: F) -> Generator
+        where F: Fn(usize) -> EncodeNum + 'static
+    {
+        Generator { generate: Rc::new(generate) }
+    }
+}
+
+impl fmt::Debug for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Generator")
+    }
+}
+
+impl Keystream for Generator {
+    fn start(&self) -> Box<Iterator<Item = EncodeNum>> {
+        let generate = self.generate.clone();
+        Box::new((0..).map(move |i| generate(i)))
+    }
+}
+
+/// How a cipher combines a message symbol with a key symbol,
+/// given the size of the alphabet.
+pub type Combine = Box<Fn(&EncodeNum, &EncodeNum, &usize) -> EncodeNum>;
+
+/// Runs `combine` along a keystream, one message symbol at a time.
+pub struct KeystreamState {
+    stream: Box<Iterator<Item = EncodeNum>>,
+    size: usize,
+    position: usize,
+    combine: Combine,
+    name: &'static str,
+}
+
+impl KeystreamState {
+    pub fn new(keystream: &Keystream,
+               size: usize,
+               combine: Combine,
+               name: &'static str)
+               -> KeystreamState {
+        KeystreamState {
+            stream: keystream.start(),
+            size: size,
+            position: 0,
+            combine: combine,
+            name: name,
+        }
+    }
+}
+
+impl SymbolTransform for KeystreamState {
+    fn transform(&mut self, symbol: EncodeNum) -> Result<EncodeNum> {
+        let key_num = match self.stream.next() {
+            Some(k) => EncodeNum::from(u64::from(k) % self.size as u64),
+            None => return Err(Error::KeyExhausted(self.position)),
+        };
+        self.position += 1;
+        let result = (self.combine)(&symbol, &key_num, &self.size);
+        trace!("{} m({}) k({}) -> c({})", self.name, symbol, key_num, result);
+        Ok(result)
+    }
+
+    fn skip(&mut self) -> Result<()> {
+        self.stream.next();
+        self.position += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::EncodeNum;
+
+    fn first(keystream: &Keystream, n: usize) -> Vec<u64> {
+        keystream.start().take(n).map(u64::from).collect()
+    }
+
+    fn nums(v: &[u64]) -> Vec<EncodeNum> {
+        v.iter().map(|n| EncodeNum::from(*n)).collect()
+    }
+
+    #[test]
+    fn repeating_cycles() {
+        assert_eq!(first(&Repeating::new(nums(&[3, 1])).unwrap(), 5),
+                   vec![3, 1, 3, 1, 3]);
+    }
+
+    #[test]
+    fn finite_runs_out() {
+        assert_eq!(first(&Finite::new(nums(&[3, 1])).unwrap(), 5), vec![3, 1]);
+    }
+
+    #[test]
+    fn trithemius_counts_up() {
+        assert_eq!(first(&Progressive::trithemius(), 4), vec![0, 1, 2, 3]);
+        assert_eq!(first(&Progressive::new(5, 2), 3), vec![5, 7, 9]);
+    }
+
+    #[test]
+    fn incrementing_moves_each_period() {
+        assert_eq!(first(&Incrementing::new(nums(&[0, 10]), 1).unwrap(), 6),
+                   vec![0, 10, 1, 11, 2, 12]);
+    }
+
+    #[test]
+    fn chain_addition() {
+        let chain = ChainAddition::new(nums(&[7, 7, 6, 5, 1]), 10).unwrap();
+        assert_eq!(first(&chain, 15),
+                   vec![7, 7, 6, 5, 1, 4, 3, 1, 6, 5, 7, 4, 7, 1, 2]);
+    }
+
+    #[test]
+    fn generator_by_position() {
+        let squares = Generator::new(|i| EncodeNum::from((i * i) as u64));
+        assert_eq!(first(&squares, 4), vec![0, 1, 4, 9]);
+        // Every start begins again from the first position.
+        assert_eq!(first(&squares, 2), vec![0, 1]);
+    }
+}
@@ -0,0 +1,178 @@
+//! Classical ciphers which work on the numbers an `Encoding`
+//! assigns to its characters.
+//!
+//! Every algorithm implements `Cipher`, so the CLI and `Encoding`
+//! can drive any of them the same way.
+
+use super::{Action, EncodeNum, Encoding, Error, Result};
+
+mod keystream;
+mod vigenere;
+mod autokey;
+mod beaufort;
+mod running_key;
+mod affine;
+mod substitution;
+mod quagmire;
+mod digraph;
+mod playfair;
+mod hill;
+mod columnar;
+mod transposition;
+mod polybius;
+mod bifid;
+mod adfgx;
+mod porta;
+mod gronsfeld;
+mod checkerboard;
+mod nihilist;
+mod squares;
+mod enigma;
+
+pub use self::keystream::{ChainAddition, Incrementing, Keystream};
+// Keystreams the CLI has no flags for, kept for library users.
+#[allow(unused_imports)]
+pub use self::keystream::{Finite, Generator, Progressive, Repeating};
+pub use self::vigenere::Vigenere;
+pub use self::autokey::Autokey;
+pub use self::beaufort::{Beaufort, VariantBeaufort};
+pub use self::running_key::RunningKey;
+pub use self::affine::Affine;
+pub use self::substitution::Substitution;
+pub use self::quagmire::Quagmire;
+pub use self::playfair::Playfair;
+pub use self::hill::Hill;
+pub use self::columnar::{Columnar, DoubleColumnar};
+pub use self::transposition::{RailFence, Route, RoutePath, Scytale};
+pub use self::polybius::{Coordinates, Polybius, PolybiusSquare};
+pub use self::bifid::{Bifid, Trifid};
+pub use self::adfgx::Adfgx;
+pub use self::porta::Porta;
+pub use self::gronsfeld::Gronsfeld;
+pub use self::squares::{FourSquare, TwoSquare, TwoSquareLayout};
+pub use self::checkerboard::{StraddlingCheckerboard, Vic};
+pub use self::nihilist::Nihilist;
+pub use self::enigma::Enigma;
+
+/// Names accepted by the CLI's `--cipher` flag.
+pub const CIPHERS: [&'static str; 32] = ["vigenere",
+                                          "autokey",
+                                          "beaufort",
+                                          "variant-beaufort",
+                                          "running-key",
+                                          "affine",
+                                          "substitution",
+                                          "quagmire1",
+                                          "quagmire2",
+                                          "quagmire3",
+                                          "quagmire4",
+                                          "playfair",
+                                          "hill",
+                                          "columnar",
+                                          "double-columnar",
+                                          "rail-fence",
+                                          "scytale",
+                                          "route",
+                                          "polybius",
+                                          "bifid",
+                                          "trifid",
+                                          "adfgx",
+                                          "adfgvx",
+                                          "porta",
+                                          "gronsfeld",
+                                          "trithemius",
+                                          "four-square",
+                                          "two-square",
+                                          "checkerboard",
+                                          "nihilist",
+                                          "vic",
+                                          "enigma"];
+
+/// The ciphers in `CIPHERS` that write letters as digits, so their
+/// ciphertext is in the encoding given by `--digit-encoding`.
+pub const NUMERIC_CIPHERS: [&'static str; 3] = ["checkerboard", "nihilist", "vic"];
+
+/// An algorithm for turning plaintext into ciphertext and back.
+pub trait Cipher {
+    fn encrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>>;
+
+    fn decrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>>;
+
+    /// Ciphers which work on one symbol at a time can hand out their state,
+    /// so that messages of any size can be transformed in constant memory.
+    /// Everything else is given the whole message at once.
+    fn symbolwise(&self, _encoding: &Encoding, _action: Action) -> Option<Box<SymbolTransform>> {
+        None
+    }
+}
+
+/// Running state of a symbol-at-a-time cipher.
+pub trait SymbolTransform {
+    fn transform(&mut self, symbol: EncodeNum) -> Result<EncodeNum>;
+
+    /// Step over a position without transforming anything there. Ciphers
+    /// without a key position, such as substitution, have nothing to step
+    /// over, so by default this does nothing. Ciphers whose keystream
+    /// comes from the message itself fail with `Error::CannotAdvanceKey`.
+    fn skip(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Keys need at least one symbol, or there is nothing to shift by.
+fn require_key(key: &[EncodeNum]) -> Result<()> {
+    if key.is_empty() {
+        Err(Error::EmptyKey(vec![]))
+    } else {
+        Ok(())
+    }
+}
+
+/// Length of the side of a square grid holding `size` symbols.
+fn grid_side(size: usize) -> Result<usize> {
+    let side = (size as f64).sqrt().round() as usize;
+    if side * side == size && size > 0 {
+        Ok(side)
+    } else {
+        Err(Error::NotASquare(size))
+    }
+}
+
+/// Run a whole message through a `SymbolTransform`.
+pub fn transform_all(mut state: Box<SymbolTransform>,
+                     message: &[EncodeNum])
+                     -> Result<Vec<EncodeNum>> {
+    message.iter().map(|m| state.transform(*m)).collect()
+}
+
+#[cfg(test)]
+pub mod harness {
+    //! Checks every cipher should pass.
+
+    use super::Cipher;
+    use super::super::{Action, Encoding};
+
+    fn run(cipher: &Cipher, e: &Encoding, text: &str, action: Action) -> String {
+        let message = e.vectorize_string(text).unwrap();
+        let whole = match action {
+            Action::Encrypt => cipher.encrypt(e, &message).unwrap(),
+            Action::Decrypt => cipher.decrypt(e, &message).unwrap(),
+        };
+        if let Some(state) = cipher.symbolwise(e, action) {
+            assert_eq!(super::transform_all(state, &message).unwrap(), whole);
+        }
+        whole.iter().map(|n| e.number_to_char(n).unwrap()).collect()
+    }
+
+    /// Encrypting `plaintext` gives `ciphertext`, and decrypting gives it back.
+    pub fn known_vector(cipher: &Cipher, e: &Encoding, plaintext: &str, ciphertext: &str) {
+        assert_eq!(run(cipher, e, plaintext, Action::Encrypt), ciphertext);
+        assert_eq!(run(cipher, e, ciphertext, Action::Decrypt), plaintext);
+    }
+
+    /// Decrypting the encryption of `plaintext` gives it back.
+    pub fn roundtrip(cipher: &Cipher, e: &Encoding, plaintext: &str) {
+        let ciphertext = run(cipher, e, plaintext, Action::Encrypt);
+        assert_eq!(run(cipher, e, &ciphertext, Action::Decrypt), plaintext);
+    }
+}
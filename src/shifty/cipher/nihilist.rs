@@ -0,0 +1,156 @@
+//! The Nihilist cipher, which writes both the message and a repeating
+//! keyword as numbers read off a keyed Polybius square, and adds them.
+//!
+//! Rows and columns are counted from 1, so on a 5x5 square every
+//! symbol is a number from 11 to 55 and every sum is between 22 and 110.
+//! The sums are written with the digits of a second `Encoding`, all
+//! padded to the width of the largest, so they read back without
+//! separators.
+
+use super::Cipher;
+use super::polybius::PolybiusSquare;
+use super::super::{EncodeNum, Encoding, Error, Result};
+
+#[derive(Debug, Clone)]
+pub struct Nihilist {
+    square: PolybiusSquare,
+    /// The number each symbol of the keyword stands for
+    key: Vec<u64>,
+    /// Number of digits, the size of the numeric encoding
+    base: u64,
+    /// Digits each sum is written with
+    width: usize,
+}
+
+impl Nihilist {
+    /// The square is filled from `square_keyword` and holds the symbols
+    /// of `encoding`. Symbols of `digits` stand for the numbers 0, 1,
+    /// 2... in the order of that encoding, and there have to be more
+    /// of them than rows of the square.
+    pub fn new(encoding: &Encoding,
+               square_keyword: &str,
+               keyword: &str,
+               digits: &Encoding)
+               -> Result<Nihilist> {
+        let square = try!(PolybiusSquare::new(encoding, square_keyword));
+        let side = square.side();
+        if digits.size() <= side {
+            return Err(Error::TooFewDigits(digits.size(), side));
+        }
+        let base = digits.size() as u64;
+        let keyword = try!(encoding.vectorize_string(keyword));
+        try!(super::require_key(&keyword));
+
+        let largest = 2 * (side as u64 * base + side as u64);
+        let mut width = 1;
+        let mut n = largest / base;
+        while n > 0 {
+            width += 1;
+            n /= base;
+        }
+        let mut nihilist = Nihilist {
+            square: square,
+            key: Vec::with_capacity(keyword.len()),
+            base: base,
+            width: width,
+        };
+        nihilist.key = keyword.iter().map(|k| nihilist.number(*k)).collect();
+        Ok(nihilist)
+    }
+
+    /// The row and column of `symbol`, counted from 1, as a two digit number.
+    fn number(&self, symbol: EncodeNum) -> u64 {
+        let coordinates = self.square.coordinates(symbol);
+        (coordinates[0] as u64 + 1) * self.base + coordinates[1] as u64 + 1
+    }
+
+    /// The symbol `number` stands for, if it is on the square.
+    fn symbol(&self, number: u64) -> Option<EncodeNum> {
+        let side = self.square.side() as u64;
+        let (row, column) = (number / self.base, number % self.base);
+        if row < 1 || row > side || column < 1 || column > side {
+            return None;
+        }
+        Some(self.square.symbol(&[row as usize - 1, column as usize - 1]))
+    }
+}
+
+impl Cipher for Nihilist {
+    fn encrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        let mut digits = Vec::with_capacity(message.len() * self.width);
+        for (m, k) in message.iter().zip(self.key.iter().cycle()) {
+            let mut sum = self.number(*m) + k;
+            let mut written = vec![EncodeNum::from(0); self.width];
+            for d in written.iter_mut().rev() {
+                *d = EncodeNum::from(sum % self.base);
+                sum /= self.base;
+            }
+            digits.extend(written);
+        }
+        Ok(digits)
+    }
+
+    fn decrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        if message.len() % self.width != 0 {
+            return Err(Error::NotWholeBlocks(message.len(), self.width));
+        }
+        let mut plaintext = Vec::with_capacity(message.len() / self.width);
+        for (digits, k) in message.chunks(self.width).zip(self.key.iter().cycle()) {
+            let sum = digits.iter().fold(0, |n, d| n * self.base + u64::from(*d));
+            match sum.checked_sub(*k).and_then(|n| self.symbol(n)) {
+                Some(symbol) => plaintext.push(symbol),
+                None => return Err(Error::NotACode(digits.to_vec())),
+            }
+        }
+        Ok(plaintext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::{digits, playfair, playfair_6x6, Encoding, Error};
+
+    #[test]
+    fn nihilist_vector() {
+        let (e, d) = (playfair(), digits());
+        let n = Nihilist::new(&e, "zebras", "russian", &d).unwrap();
+        let message = e.vectorize_string("dynamitewinterpalace").unwrap();
+        let expected = d.vectorize_string("037106062036067047086026104053\
+                                           062077027055057066055036054027")
+            .unwrap();
+        assert_eq!(n.encrypt(&e, &message).unwrap(), expected);
+        assert_eq!(n.decrypt(&d, &expected).unwrap(), message);
+    }
+
+    #[test]
+    fn six_by_six_roundtrip() {
+        let (e, d) = (playfair_6x6(), digits());
+        let n = Nihilist::new(&e, "nihilist", "key1", &d).unwrap();
+        let message = e.vectorize_string("meetatgate7at2300").unwrap();
+        let ciphertext = n.encrypt(&e, &message).unwrap();
+        assert_eq!(n.decrypt(&d, &ciphertext).unwrap(), message);
+    }
+
+    #[test]
+    fn reject_sum_off_the_square() {
+        let (e, d) = (playfair(), digits());
+        let n = Nihilist::new(&e, "zebras", "russian", &d).unwrap();
+        // The key's first number is 14, which leaves 19: column 9 of row 1.
+        match n.decrypt(&d, &d.vectorize_string("033").unwrap()) {
+            Err(Error::NotACode(_)) => (),
+            Ok(_) => panic!("We read a letter from off the square!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn reject_too_few_digits() {
+        let d = Encoding::parse(r#"alphabet = ["0", "1", "2", "3", "4"]"#).unwrap();
+        match Nihilist::new(&playfair(), "zebras", "russian", &d) {
+            Err(Error::TooFewDigits(5, 5)) => (),
+            Ok(_) => panic!("We wrote row 5 with the digits 0 to 4!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! The Playfair cipher, which swaps pairs of letters using the
+//! corners of rectangles drawn on a keyed grid.
+//!
+//! The grid holds the alphabet of the `Encoding`, so it has to be
+//! a square: the built-in `playfair` encoding fills a 5x5 grid by
+//! writing J as I, and `playfair_6x6` adds the digits to all 26 letters.
+
+use super::Cipher;
+use super::digraph::Digraphs;
+use super::super::{Action, EncodeNum, Encoding, Result};
+
+#[derive(Debug, Clone)]
+pub struct Playfair {
+    side: usize,
+    /// Symbols of the grid, row by row
+    grid: Vec<EncodeNum>,
+    /// Where each symbol sits in `grid`
+    position: Vec<usize>,
+    digraphs: Digraphs,
+}
+
+impl Playfair {
+    /// The grid is filled with `keyword` and then the rest of the alphabet.
+    /// Messages are split into pairs as `Digraphs` does, with `fillers`.
+    pub fn new(encoding: &Encoding, keyword: &str, fillers: &str) -> Result<Playfair> {
+        let side = try!(super::grid_side(encoding.size()));
+        let digraphs = try!(Digraphs::new(encoding, fillers));
+        let grid = try!(encoding.keyed_alphabet(keyword));
+        let mut position = vec![0; grid.len()];
+        for (i, n) in grid.iter().enumerate() {
+            position[u64::from(*n) as usize] = i;
+        }
+        Ok(Playfair {
+            side: side,
+            grid: grid,
+            position: position,
+            digraphs: digraphs,
+        })
+    }
+
+    /// Have decryption guess which fillers were added and take them out.
+    pub fn stripping_fillers(mut self) -> Playfair {
+        self.digraphs = self.digraphs.stripping_fillers();
+        self
+    }
+
+    fn transform_pair(&self, a: EncodeNum, b: EncodeNum, action: Action) -> [EncodeNum; 2] {
+        let side = self.side;
+        // Moving back one step is the same as moving forward side - 1.
+        let step = match action {
+            Action::Encrypt => 1,
+            Action::Decrypt => side - 1,
+        };
+        let pa = self.position[u64::from(a) as usize];
+        let pb = self.position[u64::from(b) as usize];
+        let (ra, ca) = (pa / side, pa % side);
+        let (rb, cb) = (pb / side, pb % side);
+        let at = |r: usize, c: usize| self.grid[r * side + c];
+        if ra == rb {
+            [at(ra, (ca + step) % side), at(rb, (cb + step) % side)]
+        } else if ca == cb {
+            [at((ra + step) % side, ca), at((rb + step) % side, cb)]
+        } else {
+            [at(ra, cb), at(rb, ca)]
+        }
+    }
+}
+
+impl Cipher for Playfair {
+    fn encrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(self.digraphs.encrypt(message, |a, b| self.transform_pair(a, b, Action::Encrypt)))
+    }
+
+    fn decrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        self.digraphs.decrypt(message, |a, b| self.transform_pair(a, b, Action::Decrypt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{alpha, playfair, playfair_6x6, Error};
+
+    #[test]
+    fn wikipedia_vector() {
+        let e = playfair();
+        let p = Playfair::new(&e, "playfairexample", "xq").unwrap();
+        harness::known_vector(&p,
+                              &e,
+                              "hidethegoldinthetrexestump",
+                              "bmodzbxdnabekudmuixmmouvif");
+    }
+
+    #[test]
+    fn strip_fillers() {
+        let e = playfair();
+        let p = Playfair::new(&e, "playfairexample", "xq").unwrap().stripping_fillers();
+        let message = e.vectorize_string("hidethegoldinthetreestump").unwrap();
+        let ciphertext = p.encrypt(&e, &message).unwrap();
+        assert_eq!(p.decrypt(&e, &ciphertext).unwrap(), message);
+    }
+
+    #[test]
+    fn six_by_six() {
+        let e = playfair_6x6();
+        let p = Playfair::new(&e, "playfair1234", "x").unwrap().stripping_fillers();
+        let message = e.vectorize_string("meetmeat0930bythe11thtree").unwrap();
+        let ciphertext = p.encrypt(&e, &message).unwrap();
+        assert_eq!(p.decrypt(&e, &ciphertext).unwrap(), message);
+    }
+
+    #[test]
+    fn reject_non_square_alphabet() {
+        match Playfair::new(&alpha(), "keyword", "x") {
+            Err(Error::NotASquare(26)) => (),
+            Ok(_) => panic!("We built a grid from 26 letters!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn reject_odd_ciphertext() {
+        let e = playfair();
+        let p = Playfair::new(&e, "keyword", "x").unwrap();
+        match p.decrypt(&e, &e.vectorize_string("abc").unwrap()) {
+            Err(Error::NotWholeBlocks(3, 2)) => (),
+            Ok(_) => panic!("We decrypted half a pair!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! The Polybius square, which gives every symbol of the alphabet a
+//! row and column in a keyed grid, and the cipher that writes each
+//! symbol as its pair of coordinates.
+//!
+//! Bifid, Trifid and ADFGVX all start from a square (or cube) like this.
+
+use super::Cipher;
+use super::super::{EncodeNum, Encoding, Error, Result};
+
+/// The alphabet of an `Encoding` laid out in a keyed grid, `side`
+/// symbols along each of its `dimensions`.
+#[derive(Debug, Clone)]
+pub struct PolybiusSquare {
+    side: usize,
+    dimensions: usize,
+    /// Symbols of the grid, last coordinate changing fastest
+    grid: Vec<EncodeNum>,
+    /// Where each symbol sits in `grid`
+    position: Vec<usize>,
+}
+
+impl PolybiusSquare {
+    /// A square grid filled with `keyword` and then the rest of the
+    /// alphabet, which needs a square number of symbols.
+    pub fn new(encoding: &Encoding, keyword: &str) -> Result<PolybiusSquare> {
+        let side = try!(super::grid_side(encoding.size()));
+        PolybiusSquare::with_dimensions(encoding, keyword, side, 2)
+    }
+
+    /// A cube for Trifid, which needs a cube number of symbols.
+    pub fn cube(encoding: &Encoding, keyword: &str) -> Result<PolybiusSquare> {
+        let size = encoding.size();
+        let side = (size as f64).cbrt().round() as usize;
+        if side == 0 || side * side * side != size {
+            return Err(Error::NotACube(size));
+        }
+        PolybiusSquare::with_dimensions(encoding, keyword, side, 3)
+    }
+
+    fn with_dimensions(encoding: &Encoding,
+                       keyword: &str,
+                       side: usize,
+                       dimensions: usize)
+                       -> Result<PolybiusSquare> {
+        let grid = try!(encoding.keyed_alphabet(keyword));
+        let mut position = vec![0; grid.len()];
+        for (i, n) in grid.iter().enumerate() {
+            position[u64::from(*n) as usize] = i;
+        }
+        Ok(PolybiusSquare {
+            side: side,
+            dimensions: dimensions,
+            grid: grid,
+            position: position,
+        })
+    }
+
+    /// Symbols along each edge of the grid.
+    pub fn side(&self) -> usize {
+        self.side
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Where `symbol` sits, as row then column (layer, row, column for a cube).
+    pub fn coordinates(&self, symbol: EncodeNum) -> Vec<usize> {
+        let mut i = self.position[u64::from(symbol) as usize];
+        let mut coordinates = vec![0; self.dimensions];
+        for c in coordinates.iter_mut().rev() {
+            *c = i % self.side;
+            i /= self.side;
+        }
+        coordinates
+    }
+
+    /// The symbol sitting at `coordinates`.
+    pub fn symbol(&self, coordinates: &[usize]) -> EncodeNum {
+        self.grid[coordinates.iter().fold(0, |i, c| i * self.side + c)]
+    }
+}
+
+/// Coordinates written as symbols of the encoding, such as "adfgx".
+#[derive(Debug, Clone)]
+pub struct Coordinates {
+    symbols: Vec<EncodeNum>,
+    /// Which coordinate each symbol of the encoding stands for
+    index: Vec<Option<usize>>,
+}
+
+impl Coordinates {
+    /// `symbols` needs one distinct symbol for each row of the square.
+    pub fn new(encoding: &Encoding, symbols: &str, side: usize) -> Result<Coordinates> {
+        let symbols = try!(encoding.vectorize_string(symbols));
+        let mut index = vec![None; encoding.size()];
+        for (i, n) in symbols.iter().enumerate() {
+            index[u64::from(*n) as usize] = Some(i);
+        }
+        let distinct = index.iter().filter(|i| i.is_some()).count();
+        if distinct != symbols.len() || symbols.len() != side {
+            return Err(Error::WrongCoordinates(side, distinct));
+        }
+        Ok(Coordinates {
+            symbols: symbols,
+            index: index,
+        })
+    }
+
+    /// The first `side` symbols of the encoding.
+    pub fn first(encoding: &Encoding, side: usize) -> Result<Coordinates> {
+        let symbols: String = try!((0..side)
+            .map(|i| encoding.number_to_char(&EncodeNum::from(i as u64)))
+            .collect());
+        Coordinates::new(encoding, &symbols, side)
+    }
+
+    pub fn write(&self, coordinate: usize) -> EncodeNum {
+        self.symbols[coordinate]
+    }
+
+    pub fn read(&self, encoding: &Encoding, symbol: EncodeNum) -> Result<usize> {
+        match self.index[u64::from(symbol) as usize] {
+            Some(i) => Ok(i),
+            None => Err(Error::NotACoordinate(try!(encoding.number_to_char(&symbol)))),
+        }
+    }
+}
+
+/// Every symbol becomes its row and column, written with `coordinates`.
+#[derive(Debug, Clone)]
+pub struct Polybius {
+    square: PolybiusSquare,
+    coordinates: Coordinates,
+}
+
+impl Polybius {
+    pub fn new(square: PolybiusSquare, coordinates: Coordinates) -> Polybius {
+        Polybius {
+            square: square,
+            coordinates: coordinates,
+        }
+    }
+
+    /// Split every symbol into its coordinate symbols.
+    pub fn fractionate(&self, message: &[EncodeNum]) -> Vec<EncodeNum> {
+        message.iter()
+            .flat_map(|m| self.square.coordinates(*m))
+            .map(|c| self.coordinates.write(c))
+            .collect()
+    }
+
+    /// Join coordinate symbols back up into the symbols they point at.
+    pub fn unfractionate(&self,
+                         encoding: &Encoding,
+                         message: &[EncodeNum])
+                         -> Result<Vec<EncodeNum>> {
+        let dimensions = self.square.dimensions();
+        if message.len() % dimensions != 0 {
+            return Err(Error::NotWholeBlocks(message.len(), dimensions));
+        }
+        let mut plaintext = Vec::with_capacity(message.len() / dimensions);
+        for block in message.chunks(dimensions) {
+            let mut coordinates = Vec::with_capacity(dimensions);
+            for symbol in block {
+                coordinates.push(try!(self.coordinates.read(encoding, *symbol)));
+            }
+            plaintext.push(self.square.symbol(&coordinates));
+        }
+        Ok(plaintext)
+    }
+}
+
+impl Cipher for Polybius {
+    fn encrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(self.fractionate(message))
+    }
+
+    fn decrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        self.unfractionate(encoding, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{alpha_space, playfair, Error};
+
+    #[test]
+    fn square_coordinates() {
+        let e = playfair();
+        let square = PolybiusSquare::new(&e, "").unwrap();
+        let w = e.char_to_number(&'w').unwrap();
+        assert_eq!(square.coordinates(w), vec![4, 1]);
+        assert_eq!(square.symbol(&[4, 1]), w);
+    }
+
+    #[test]
+    fn cube_coordinates() {
+        let e = alpha_space();
+        let cube = PolybiusSquare::cube(&e, "").unwrap();
+        let m = e.char_to_number(&'m').unwrap();
+        assert_eq!(cube.coordinates(m), vec![1, 1, 0]);
+        assert_eq!(cube.symbol(&[1, 1, 0]), m);
+    }
+
+    #[test]
+    fn polybius_vector() {
+        let e = playfair();
+        let square = PolybiusSquare::new(&e, "").unwrap();
+        let p = Polybius::new(square, Coordinates::new(&e, "abcde", 5).unwrap());
+        harness::known_vector(&p, &e, "bat", "abaadd");
+    }
+
+    #[test]
+    fn default_coordinates() {
+        let e = playfair();
+        let square = PolybiusSquare::new(&e, "keyword").unwrap();
+        let p = Polybius::new(square, Coordinates::first(&e, 5).unwrap());
+        harness::roundtrip(&p, &e, "meetmeatthegate");
+    }
+
+    #[test]
+    fn reject_repeated_coordinates() {
+        match Coordinates::new(&playfair(), "adfga", 5) {
+            Err(Error::WrongCoordinates(5, 4)) => (),
+            Ok(_) => panic!("We accepted a coordinate twice!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn reject_stray_ciphertext() {
+        let e = playfair();
+        let square = PolybiusSquare::new(&e, "").unwrap();
+        let p = Polybius::new(square, Coordinates::new(&e, "adfgx", 5).unwrap());
+        match p.decrypt(&e, &e.vectorize_string("adfz").unwrap()) {
+            Err(Error::NotACoordinate('z')) => (),
+            Ok(_) => panic!("We decrypted a coordinate that does not exist!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! The Porta cipher, a reciprocal cipher where each pair of key
+//! letters picks one of 13 alphabets.
+//!
+//! The alphabet is split into halves, and every alphabet swaps a symbol
+//! in the first half with one in the second. Any `Encoding` with an even
+//! number of symbols works, giving half as many alphabets as symbols.
+
+use super::{Cipher, SymbolTransform};
+use super::keystream::{Keystream, KeystreamState, Repeating};
+use super::super::{Action, EncodeNum, Encoding, Error, Result};
+
+/// Swap `message` into the other half of the alphabet, along the
+/// alphabet `key` picks. Doing it twice gets the message back.
+pub fn reciprocal(message: &EncodeNum, key: &EncodeNum, size: &usize) -> EncodeNum {
+    let half = *size / 2;
+    let m = u64::from(*message) as usize;
+    let shift = (u64::from(*key) as usize / 2) % half;
+    let c = if m < half {
+        half + (m + shift) % half
+    } else {
+        (m - half + half - shift) % half
+    };
+    EncodeNum::from(c as u64)
+}
+
+#[derive(Debug)]
+pub struct Porta {
+    keystream: Box<Keystream>,
+}
+
+impl Porta {
+    pub fn new(encoding: &Encoding, key: Vec<EncodeNum>) -> Result<Porta> {
+        Porta::with_keystream(encoding, Box::new(try!(Repeating::new(key))))
+    }
+
+    pub fn from_key(encoding: &Encoding, keytext: &str) -> Result<Porta> {
+        Porta::new(encoding, try!(encoding.vectorize_string(keytext)))
+    }
+
+    pub fn with_keystream(encoding: &Encoding, keystream: Box<Keystream>) -> Result<Porta> {
+        if encoding.size() % 2 != 0 {
+            return Err(Error::OddAlphabet(encoding.size()));
+        }
+        Ok(Porta { keystream: keystream })
+    }
+}
+
+impl Cipher for Porta {
+    fn encrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        super::transform_all(self.symbolwise(encoding, Action::Encrypt).unwrap(), message)
+    }
+
+    fn decrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        super::transform_all(self.symbolwise(encoding, Action::Decrypt).unwrap(), message)
+    }
+
+    fn symbolwise(&self, encoding: &Encoding, _action: Action) -> Option<Box<SymbolTransform>> {
+        let size = encoding.size();
+        Some(Box::new(KeystreamState::new(&*self.keystream, size, Box::new(reciprocal), "Porta")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{alpha, alpha_space, alphanumeric_space, Error};
+
+    #[test]
+    fn porta_vector() {
+        let e = alpha();
+        let p = Porta::from_key(&e, "fortification").unwrap();
+        harness::known_vector(&p,
+                              &e,
+                              "defendtheeastwallofthecastle",
+                              "synnjscvrnrlahutukucvryrlany");
+    }
+
+    #[test]
+    fn porta_is_self_reciprocal() {
+        let e = alphanumeric_space();
+        let p = Porta::from_key(&e, "k3y").unwrap();
+        let m = e.vectorize_string("meet me at 9").unwrap();
+        let c = p.encrypt(&e, &m).unwrap();
+        assert_eq!(p.encrypt(&e, &c).unwrap(), m);
+    }
+
+    #[test]
+    fn reject_odd_alphabet() {
+        let e = alpha_space();
+        match Porta::from_key(&e, "key") {
+            Err(Error::OddAlphabet(27)) => (),
+            Ok(_) => panic!("We paired up an odd number of characters!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
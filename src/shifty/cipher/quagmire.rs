@@ -0,0 +1,204 @@
+//! The Quagmire ciphers, Vigenère with keyword-mixed alphabets.
+//!
+//! Each indicator symbol slides the ciphertext alphabet along the
+//! plaintext alphabet until it sits under the first symbol of the
+//! `Encoding`. The four variants differ only in which alphabets are mixed:
+//!
+//! * Quagmire I: mixed plaintext, straight ciphertext
+//! * Quagmire II: straight plaintext, mixed ciphertext
+//! * Quagmire III: the same mixed alphabet for both
+//! * Quagmire IV: different mixed alphabets for each
+
+use std::rc::Rc;
+use super::{Cipher, SymbolTransform};
+use super::keystream::{Keystream, KeystreamState, Repeating};
+use super::super::{Action, EncodeNum, Encoding, Result};
+
+/// The two mixed alphabets, shared with every `KeystreamState` running them.
+#[derive(Debug)]
+struct Alphabets {
+    /// Symbol at each position of the plaintext alphabet
+    plain: Vec<EncodeNum>,
+    /// Position of each symbol in the plaintext alphabet
+    plain_position: Vec<usize>,
+    cipher: Vec<EncodeNum>,
+    cipher_position: Vec<usize>,
+}
+
+/// Where each symbol sits in `alphabet`.
+fn positions(alphabet: &[EncodeNum]) -> Vec<usize> {
+    let mut position = vec![0; alphabet.len()];
+    for (i, n) in alphabet.iter().enumerate() {
+        position[u64::from(*n) as usize] = i;
+    }
+    position
+}
+
+impl Alphabets {
+    /// Slide the ciphertext alphabet until the indicator symbol `key`
+    /// sits under the first symbol of the encoding, then read `symbol`
+    /// off the other alphabet.
+    fn transform_num(&self, symbol: &EncodeNum, key: &EncodeNum, action: Action) -> EncodeNum {
+        let size = self.plain.len();
+        let anchor = self.plain_position[0];
+        let shift = (self.cipher_position[u64::from(*key) as usize] + size - anchor) % size;
+        let n = u64::from(*symbol) as usize;
+        match action {
+            Action::Encrypt => self.cipher[(self.plain_position[n] + shift) % size],
+            Action::Decrypt => self.plain[(self.cipher_position[n] + size - shift) % size],
+        }
+    }
+}
+
+/// Mixed alphabets slid along by an indicator, which repeats unless
+/// another `Keystream` is given.
+#[derive(Debug)]
+pub struct Quagmire {
+    alphabets: Rc<Alphabets>,
+    keystream: Box<Keystream>,
+}
+
+impl Quagmire {
+    /// Both alphabets must hold every symbol of the encoding exactly once,
+    /// as `Encoding::keyed_alphabet` does.
+    fn new(plain: Vec<EncodeNum>,
+           cipher: Vec<EncodeNum>,
+           indicator: Vec<EncodeNum>)
+           -> Result<Quagmire> {
+        let keystream = try!(Repeating::new(indicator));
+        let plain_position = positions(&plain);
+        let cipher_position = positions(&cipher);
+        Ok(Quagmire {
+            alphabets: Rc::new(Alphabets {
+                plain: plain,
+                plain_position: plain_position,
+                cipher: cipher,
+                cipher_position: cipher_position,
+            }),
+            keystream: Box::new(keystream),
+        })
+    }
+
+    pub fn quagmire1(encoding: &Encoding, keyword: &str, indicator: &str) -> Result<Quagmire> {
+        Quagmire::new(try!(encoding.keyed_alphabet(keyword)),
+                      try!(encoding.keyed_alphabet("")),
+                      try!(encoding.vectorize_string(indicator)))
+    }
+
+    pub fn quagmire2(encoding: &Encoding, keyword: &str, indicator: &str) -> Result<Quagmire> {
+        Quagmire::new(try!(encoding.keyed_alphabet("")),
+                      try!(encoding.keyed_alphabet(keyword)),
+                      try!(encoding.vectorize_string(indicator)))
+    }
+
+    pub fn quagmire3(encoding: &Encoding, keyword: &str, indicator: &str) -> Result<Quagmire> {
+        let alphabet = try!(encoding.keyed_alphabet(keyword));
+        Quagmire::new(alphabet.clone(),
+                      alphabet,
+                      try!(encoding.vectorize_string(indicator)))
+    }
+
+    pub fn quagmire4(encoding: &Encoding,
+                     plain_keyword: &str,
+                     cipher_keyword: &str,
+                     indicator: &str)
+                     -> Result<Quagmire> {
+        Quagmire::new(try!(encoding.keyed_alphabet(plain_keyword)),
+                      try!(encoding.keyed_alphabet(cipher_keyword)),
+                      try!(encoding.vectorize_string(indicator)))
+    }
+
+    /// The same alphabets, with the indicator symbols taken from
+    /// `keystream` instead.
+    pub fn with_keystream(self, keystream: Box<Keystream>) -> Quagmire {
+        Quagmire { keystream: keystream, ..self }
+    }
+}
+
+impl Cipher for Quagmire {
+    fn encrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        super::transform_all(self.symbolwise(encoding, Action::Encrypt).unwrap(), message)
+    }
+
+    fn decrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        super::transform_all(self.symbolwise(encoding, Action::Decrypt).unwrap(), message)
+    }
+
+    fn symbolwise(&self, _encoding: &Encoding, action: Action) -> Option<Box<SymbolTransform>> {
+        let alphabets = self.alphabets.clone();
+        let size = alphabets.plain.len();
+        let combine = Box::new(move |m: &EncodeNum, k: &EncodeNum, _: &usize| {
+            alphabets.transform_num(m, k, action)
+        });
+        Some(Box::new(KeystreamState::new(&*self.keystream, size, combine, "Quagmire")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::keystream::Incrementing;
+    use super::super::super::{alpha, alphanumeric_space};
+
+    const PLAINTEXT: &'static str = "dontletanyonetellyouwhatyoucantencipher";
+
+    #[test]
+    fn straight_alphabets_are_vigenere() {
+        let e = alpha();
+        let vigenere = "ussepgmoepsspxgezpfybsevrclteseipvwgyiw";
+        let q1 = Quagmire::quagmire1(&e, "", "reflector").unwrap();
+        let q4 = Quagmire::quagmire4(&e, "", "", "reflector").unwrap();
+        harness::known_vector(&q1, &e, PLAINTEXT, vigenere);
+        harness::known_vector(&q4, &e, PLAINTEXT, vigenere);
+    }
+
+    #[test]
+    fn quagmire1_vector() {
+        let e = alpha();
+        let q = Quagmire::quagmire1(&e, "springfever", "flower").unwrap();
+        harness::known_vector(&q, &e, PLAINTEXT, "iujhlpqljlnmdwmdlgoxbaecuuayemqjjyyjjjh");
+    }
+
+    #[test]
+    fn quagmire2_vector() {
+        let e = alpha();
+        let q = Quagmire::quagmire2(&e, "springfever", "flower").unwrap();
+        harness::known_vector(&q, &e, PLAINTEXT, "angkofzlgtukbaweostbjieunndyekztgykmhta");
+    }
+
+    #[test]
+    fn quagmire3_vector() {
+        let e = alpha();
+        let q = Quagmire::quagmire3(&e, "springfever", "flower").unwrap();
+        harness::known_vector(&q, &e, PLAINTEXT, "azhejsmlhclxnplijmkrgsehuznyexmjhyptbjc");
+    }
+
+    #[test]
+    fn quagmire4_vector() {
+        let e = alpha();
+        let q = Quagmire::quagmire4(&e, "senorita", "percussion", "extra").unwrap();
+        harness::known_vector(&q, &e, PLAINTEXT, "uqjemvwtxpxmhecbidywkctepxbwrupljuofchz");
+    }
+
+    #[test]
+    fn custom_alphabet() {
+        let e = alphanumeric_space();
+        let q = Quagmire::quagmire4(&e, "room 1 one", "big brother", "1984").unwrap();
+        harness::roundtrip(&q, &e, "who controls the past controls the future");
+    }
+
+    #[test]
+    fn incrementing_indicator() {
+        // Moving the indicator on by one after every period is the same as
+        // spelling out the moved indicators in full.
+        let e = alpha();
+        let q = Quagmire::quagmire3(&e, "springfever", "flower").unwrap();
+        let key = e.vectorize_string("flower").unwrap();
+        let moving = q.with_keystream(Box::new(Incrementing::new(key, 1).unwrap()));
+        let spelled = Quagmire::quagmire3(&e, "springfever", "flowergmpxfs").unwrap();
+        let message = e.vectorize_string(&PLAINTEXT[..12]).unwrap();
+        assert_eq!(moving.encrypt(&e, &message).unwrap(),
+                   spelled.encrypt(&e, &message).unwrap());
+    }
+}
@@ -0,0 +1,78 @@
+//! Running-key cipher, Vigenère with a key as long as the message,
+//! usually taken from a passage of some book.
+
+use super::{Cipher, SymbolTransform, Vigenere};
+use super::keystream::Finite;
+use super::super::{Action, EncodeNum, Encoding, Result};
+
+#[derive(Debug)]
+pub struct RunningKey {
+    vigenere: Vigenere,
+}
+
+impl RunningKey {
+    pub fn new(key: Vec<EncodeNum>) -> Result<RunningKey> {
+        let keystream = try!(Finite::new(key));
+        Ok(RunningKey { vigenere: Vigenere::with_keystream(Box::new(keystream)) })
+    }
+
+    /// Normalize `text` the same way messages are, with `map_filter_string`,
+    /// then start the key `offset` symbols into it.
+    pub fn from_text(encoding: &Encoding, text: &str, offset: usize) -> Result<RunningKey> {
+        let key = try!(encoding.vectorize_string(&encoding.map_filter_string(text)));
+        let offset = ::std::cmp::min(offset, key.len());
+        RunningKey::new(key[offset..].to_vec())
+    }
+}
+
+impl Cipher for RunningKey {
+    fn encrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        self.vigenere.encrypt(encoding, message)
+    }
+
+    fn decrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        self.vigenere.decrypt(encoding, message)
+    }
+
+    fn symbolwise(&self, encoding: &Encoding, action: Action) -> Option<Box<SymbolTransform>> {
+        self.vigenere.symbolwise(encoding, action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{alpha, Error};
+
+    const BOOK: &'static str = "Errors, like straws, upon the surface flow; \
+                                He who would search for pearls must dive below.";
+
+    #[test]
+    fn key_is_normalized() {
+        let e = alpha();
+        let r = RunningKey::from_text(&e, BOOK, 0).unwrap();
+        harness::known_vector(&r, &e, "fleeatonce", "jcvsrlzvmi");
+    }
+
+    #[test]
+    fn key_offset() {
+        let e = alpha();
+        let r = RunningKey::from_text(&e, BOOK, 6).unwrap();
+        let v = super::super::Vigenere::from_key(&e, "likestra").unwrap();
+        let m = e.vectorize_string("fleeatonce").unwrap();
+        assert_eq!(r.encrypt(&e, &m[..8]).unwrap(), v.encrypt(&e, &m[..8]).unwrap());
+    }
+
+    #[test]
+    fn key_does_not_wrap() {
+        let e = alpha();
+        let r = RunningKey::from_text(&e, "abc", 0).unwrap();
+        let m = e.vectorize_string("abcd").unwrap();
+        match r.encrypt(&e, &m) {
+            Err(Error::KeyExhausted(3)) => (),
+            Ok(_) => panic!("We wrapped the key around!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
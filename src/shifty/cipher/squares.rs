@@ -0,0 +1,234 @@
+//! Delastelle's Four-square and Wheatstone's Two-square ciphers, which
+//! like Playfair swap pairs of letters for the other corners of a
+//! rectangle, but draw the rectangle across more than one keyed square.
+//!
+//! Every square holds the alphabet of the `Encoding`, so it needs a
+//! square number of symbols, such as the `playfair` encoding.
+
+use super::Cipher;
+use super::digraph::Digraphs;
+use super::polybius::PolybiusSquare;
+use super::super::{Action, EncodeNum, Encoding, Result};
+
+/// Plain squares at the top left and bottom right, and squares keyed
+/// with the two keywords at the top right and bottom left. The first
+/// letter of a pair is found in the top left and the second in the
+/// bottom right; the ciphertext is read from the other two corners.
+#[derive(Debug, Clone)]
+pub struct FourSquare {
+    plain: PolybiusSquare,
+    upper: PolybiusSquare,
+    lower: PolybiusSquare,
+    digraphs: Digraphs,
+}
+
+impl FourSquare {
+    /// Messages are split into pairs as `Digraphs` does, with `fillers`.
+    pub fn new(encoding: &Encoding,
+               first_keyword: &str,
+               second_keyword: &str,
+               fillers: &str)
+               -> Result<FourSquare> {
+        Ok(FourSquare {
+            plain: try!(PolybiusSquare::new(encoding, "")),
+            upper: try!(PolybiusSquare::new(encoding, first_keyword)),
+            lower: try!(PolybiusSquare::new(encoding, second_keyword)),
+            digraphs: try!(Digraphs::new(encoding, fillers)),
+        })
+    }
+
+    /// Have decryption guess which fillers were added and take them out.
+    pub fn stripping_fillers(mut self) -> FourSquare {
+        self.digraphs = self.digraphs.stripping_fillers();
+        self
+    }
+
+    fn transform_pair(&self, a: EncodeNum, b: EncodeNum, action: Action) -> [EncodeNum; 2] {
+        let (from_a, from_b, to_a, to_b) = match action {
+            Action::Encrypt => (&self.plain, &self.plain, &self.upper, &self.lower),
+            Action::Decrypt => (&self.upper, &self.lower, &self.plain, &self.plain),
+        };
+        let ca = from_a.coordinates(a);
+        let cb = from_b.coordinates(b);
+        [to_a.symbol(&[ca[0], cb[1]]), to_b.symbol(&[cb[0], ca[1]])]
+    }
+}
+
+impl Cipher for FourSquare {
+    fn encrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(self.digraphs.encrypt(message, |a, b| self.transform_pair(a, b, Action::Encrypt)))
+    }
+
+    fn decrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        self.digraphs.decrypt(message, |a, b| self.transform_pair(a, b, Action::Decrypt))
+    }
+}
+
+/// How the two squares of a Two-square sit next to each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TwoSquareLayout {
+    /// The first square above the second
+    Vertical,
+    /// The first square to the left of the second
+    Horizontal,
+}
+
+/// The first letter of a pair is found in the first square and the
+/// second letter in the second square.
+///
+/// Vertically, a pair in the same column is left as it is, and every
+/// other pair is read from the other corners of its rectangle in the
+/// same order. Horizontally, a pair in the same row is swapped round,
+/// and every other pair is read from the other corners starting with
+/// the one in the second square.
+#[derive(Debug, Clone)]
+pub struct TwoSquare {
+    first: PolybiusSquare,
+    second: PolybiusSquare,
+    layout: TwoSquareLayout,
+    digraphs: Digraphs,
+}
+
+impl TwoSquare {
+    /// Messages are split into pairs as `Digraphs` does, with `fillers`.
+    pub fn new(encoding: &Encoding,
+               first_keyword: &str,
+               second_keyword: &str,
+               layout: TwoSquareLayout,
+               fillers: &str)
+               -> Result<TwoSquare> {
+        Ok(TwoSquare {
+            first: try!(PolybiusSquare::new(encoding, first_keyword)),
+            second: try!(PolybiusSquare::new(encoding, second_keyword)),
+            layout: layout,
+            digraphs: try!(Digraphs::new(encoding, fillers)),
+        })
+    }
+
+    /// Have decryption guess which fillers were added and take them out.
+    pub fn stripping_fillers(mut self) -> TwoSquare {
+        self.digraphs = self.digraphs.stripping_fillers();
+        self
+    }
+
+    fn vertical(&self, a: EncodeNum, b: EncodeNum) -> [EncodeNum; 2] {
+        let ca = self.first.coordinates(a);
+        let cb = self.second.coordinates(b);
+        if ca[1] == cb[1] {
+            [a, b]
+        } else {
+            [self.first.symbol(&[ca[0], cb[1]]), self.second.symbol(&[cb[0], ca[1]])]
+        }
+    }
+
+    /// `a` is looked up in `left` and `b` in `right`, which are the
+    /// other way round when decrypting.
+    fn horizontal(&self,
+                  left: &PolybiusSquare,
+                  right: &PolybiusSquare,
+                  a: EncodeNum,
+                  b: EncodeNum)
+                  -> [EncodeNum; 2] {
+        let ca = left.coordinates(a);
+        let cb = right.coordinates(b);
+        if ca[0] == cb[0] {
+            [b, a]
+        } else {
+            [right.symbol(&[ca[0], cb[1]]), left.symbol(&[cb[0], ca[1]])]
+        }
+    }
+
+    fn transform_pair(&self, a: EncodeNum, b: EncodeNum, action: Action) -> [EncodeNum; 2] {
+        match (self.layout, action) {
+            // Vertically the rectangle comes back to where it started.
+            (TwoSquareLayout::Vertical, _) => self.vertical(a, b),
+            (TwoSquareLayout::Horizontal, Action::Encrypt) => {
+                self.horizontal(&self.first, &self.second, a, b)
+            }
+            (TwoSquareLayout::Horizontal, Action::Decrypt) => {
+                self.horizontal(&self.second, &self.first, a, b)
+            }
+        }
+    }
+}
+
+impl Cipher for TwoSquare {
+    fn encrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(self.digraphs.encrypt(message, |a, b| self.transform_pair(a, b, Action::Encrypt)))
+    }
+
+    fn decrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        self.digraphs.decrypt(message, |a, b| self.transform_pair(a, b, Action::Decrypt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{alpha, playfair, Encoding, Error};
+
+    /// The published examples leave out Q rather than merging I and J.
+    fn without_q() -> Encoding {
+        let mut e = Encoding::new();
+        for c in "abcdefghijklmnoprstuvwxyz".chars() {
+            e.insert_char(c);
+        }
+        e
+    }
+
+    #[test]
+    fn four_square_vector() {
+        let e = without_q();
+        let f = FourSquare::new(&e, "example", "keyword", "x").unwrap();
+        harness::known_vector(&f, &e, "helpmeobiwankenobi", "fygmkyhobxmfkkkimd");
+    }
+
+    #[test]
+    fn four_square_merged_letters() {
+        let e = playfair();
+        let f = FourSquare::new(&e, "example", "keyword", "xq").unwrap().stripping_fillers();
+        let message = e.vectorize_string(&e.map_filter_string("jelly beans")).unwrap();
+        let ciphertext = f.encrypt(&e, &message).unwrap();
+        assert_eq!(f.decrypt(&e, &ciphertext).unwrap(),
+                   e.vectorize_string("iellybeans").unwrap());
+    }
+
+    #[test]
+    fn two_square_vertical_vector() {
+        let e = without_q();
+        let t = TwoSquare::new(&e, "example", "keyword", TwoSquareLayout::Vertical, "x").unwrap();
+        harness::known_vector(&t, &e, "helpmeobiwankenobi", "hedlxwsdjyanhotkdg");
+    }
+
+    #[test]
+    fn two_square_horizontal_vector() {
+        let e = without_q();
+        let t = TwoSquare::new(&e, "example", "keyword", TwoSquareLayout::Horizontal, "x")
+            .unwrap();
+        // H is on row 2 of EXAMPLE and E on row 0 of KEYWORD, so the pair
+        // reads G from KEYWORD on H's row, then X from EXAMPLE on E's row.
+        harness::known_vector(&t, &e, "helpmeobiwankenobi", "gxbnempbiayrgpsebh");
+        // M and E sit in the same row of their squares, so they swap.
+        let me = e.vectorize_string("me").unwrap();
+        assert_eq!(t.encrypt(&e, &me).unwrap(), e.vectorize_string("em").unwrap());
+    }
+
+    #[test]
+    fn two_square_pads_doubles() {
+        let e = playfair();
+        let t = TwoSquare::new(&e, "example", "keyword", TwoSquareLayout::Horizontal, "x")
+            .unwrap()
+            .stripping_fillers();
+        harness::roundtrip(&t, &e, "meetmeatthetree");
+    }
+
+    #[test]
+    fn reject_non_square_alphabet() {
+        match FourSquare::new(&alpha(), "example", "keyword", "x") {
+            Err(Error::NotASquare(26)) => (),
+            Ok(_) => panic!("We built a square from 26 letters!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Simple substitution, where the alphabet is swapped
+//! for a reordering of itself.
+
+use super::{Cipher, SymbolTransform};
+use super::super::{Action, EncodeNum, Encoding, Error, Result};
+use super::super::error::ConfigError;
+use super::super::parser::{self, SubstitutionKey};
+
+#[derive(Debug, Clone)]
+pub struct Substitution {
+    /// Ciphertext symbol for each plaintext symbol
+    forward: Vec<EncodeNum>,
+    /// Plaintext symbol for each ciphertext symbol
+    backward: Vec<EncodeNum>,
+}
+
+impl Substitution {
+    /// `permutation[m]` is what plaintext symbol `m` becomes,
+    /// so it must hold every symbol of `encoding` exactly once.
+    pub fn new(encoding: &Encoding, permutation: Vec<EncodeNum>) -> Result<Substitution> {
+        let size = encoding.size();
+        let mut backward: Vec<Option<EncodeNum>> = vec![None; size];
+        let mut repeated: Vec<char> = Vec::new();
+        for (m, c) in permutation.iter().enumerate() {
+            let slot = &mut backward[u64::from(*c) as usize];
+            if slot.is_some() {
+                let repeat = try!(encoding.number_to_char(c));
+                if !repeated.contains(&repeat) {
+                    repeated.push(repeat);
+                }
+            } else {
+                *slot = Some(EncodeNum::from(m as u64));
+            }
+        }
+
+        let mut missing: Vec<char> = Vec::new();
+        for (c, m) in backward.iter().enumerate() {
+            if m.is_none() {
+                missing.push(try!(encoding.number_to_char(&EncodeNum::from(c as u64))));
+            }
+        }
+        if !missing.is_empty() || !repeated.is_empty() {
+            return Err(Error::NotAPermutation(missing, repeated));
+        }
+
+        Ok(Substitution {
+            forward: permutation,
+            backward: backward.into_iter().map(|m| m.unwrap()).collect(),
+        })
+    }
+
+    /// Every character of the alphabet, written in their new order.
+    pub fn from_permutation(encoding: &Encoding, permutation: &str) -> Result<Substitution> {
+        Substitution::new(encoding, try!(encoding.vectorize_string(permutation)))
+    }
+
+    /// The keyword, then the rest of the alphabet, as in "zebrascdfgh...".
+    pub fn from_keyword(encoding: &Encoding, keyword: &str) -> Result<Substitution> {
+        Substitution::new(encoding, try!(encoding.keyed_alphabet(keyword)))
+    }
+
+    /// Read the key from the `[substitution]` table of a TOML config,
+    /// which holds either a `keyword` or a `permutation`.
+    pub fn parse(encoding: &Encoding, toml: &str) -> Result<Substitution> {
+        let root_table = try!(parser::read_toml_string(toml));
+        match try!(parser::parse_substitution(&root_table)) {
+            Some(SubstitutionKey::Keyword(k)) => Substitution::from_keyword(encoding, &k),
+            Some(SubstitutionKey::Permutation(p)) => Substitution::from_permutation(encoding, &p),
+            None => {
+                Err(From::from(ConfigError::SchemaError("Config has no [substitution] table"
+                    .to_string())))
+            }
+        }
+    }
+
+    /// What `symbol` becomes, or what it came from when decrypting.
+    pub fn transform_num(&self, symbol: EncodeNum, action: Action) -> EncodeNum {
+        let table = match action {
+            Action::Encrypt => &self.forward,
+            Action::Decrypt => &self.backward,
+        };
+        table[u64::from(symbol) as usize]
+    }
+}
+
+struct SubstitutionState {
+    substitution: Substitution,
+    action: Action,
+}
+
+impl SymbolTransform for SubstitutionState {
+    fn transform(&mut self, symbol: EncodeNum) -> Result<EncodeNum> {
+        Ok(self.substitution.transform_num(symbol, self.action))
+    }
+}
+
+impl Cipher for Substitution {
+    fn encrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(message.iter().map(|m| self.transform_num(*m, Action::Encrypt)).collect())
+    }
+
+    fn decrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(message.iter().map(|c| self.transform_num(*c, Action::Decrypt)).collect())
+    }
+
+    fn symbolwise(&self, _encoding: &Encoding, action: Action) -> Option<Box<SymbolTransform>> {
+        Some(Box::new(SubstitutionState {
+            substitution: self.clone(),
+            action: action,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{alpha, alphanumeric_space, Error};
+
+    #[test]
+    fn keyword_vector() {
+        let e = alpha();
+        let s = Substitution::from_keyword(&e, "zebras").unwrap();
+        harness::known_vector(&s,
+                              &e,
+                              "fleeatoncewearediscovered",
+                              "siaazqlkbavazoarfpbluaoar");
+    }
+
+    #[test]
+    fn permutation_matches_keyword() {
+        let e = alpha();
+        let s = Substitution::from_permutation(&e, "zebrascdfghijklmnopqtuvwxy").unwrap();
+        harness::known_vector(&s,
+                              &e,
+                              "fleeatoncewearediscovered",
+                              "siaazqlkbavazoarfpbluaoar");
+    }
+
+    #[test]
+    fn custom_alphabet() {
+        let e = alphanumeric_space();
+        let s = Substitution::from_keyword(&e, "9 lives").unwrap();
+        harness::roundtrip(&s, &e, "meet me at the 4th gate at 9");
+    }
+
+    #[test]
+    fn reject_bad_permutation() {
+        let e = alpha();
+        match Substitution::from_permutation(&e, "zebrascdfghijklmnopqtuvwxz") {
+            Err(Error::NotAPermutation(missing, repeated)) => {
+                assert_eq!(missing, vec!['y']);
+                assert_eq!(repeated, vec!['z']);
+            }
+            Ok(_) => panic!("We accepted a key that is not a permutation!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn parse_from_toml() {
+        let test_string = r#"
+        alphabet = ["a", "b", "c", "d"]
+        [substitution]
+        permutation = "dcab"
+        "#;
+        let e = ::shifty::Encoding::parse(test_string).unwrap();
+        let s = Substitution::parse(&e, test_string).unwrap();
+        harness::known_vector(&s, &e, "abcd", "dcab");
+    }
+}
@@ -0,0 +1,272 @@
+//! Transpositions that follow a fixed pattern, where the only key is
+//! the size of the pattern: rail fence, scytale and route ciphers.
+//!
+//! Each one works out the order in which the positions of the message
+//! are read off, which depends only on the length of the message, so
+//! decrypting puts every symbol back where that order took it from.
+
+use super::Cipher;
+use super::super::{EncodeNum, Encoding, Error, Result};
+
+/// Take the symbols of `message` in `order`.
+fn gather(message: &[EncodeNum], order: &[usize]) -> Vec<EncodeNum> {
+    order.iter().map(|i| message[*i]).collect()
+}
+
+/// Undo `gather`, putting each symbol back where it was read from.
+fn scatter(message: &[EncodeNum], order: &[usize]) -> Vec<EncodeNum> {
+    let mut plaintext = vec![EncodeNum::from(0); message.len()];
+    for (symbol, i) in message.iter().zip(order) {
+        plaintext[*i] = *symbol;
+    }
+    plaintext
+}
+
+fn require_nonzero(name: &'static str, value: usize) -> Result<()> {
+    if value == 0 {
+        Err(Error::ZeroParameter(name))
+    } else {
+        Ok(())
+    }
+}
+
+/// The message zigzags down and up across `rails` rows, and each
+/// row is read off in turn.
+#[derive(Debug, Clone, Copy)]
+pub struct RailFence {
+    rails: usize,
+    /// How far into the zigzag the first symbol starts
+    offset: usize,
+}
+
+impl RailFence {
+    pub fn new(rails: usize, offset: usize) -> Result<RailFence> {
+        try!(require_nonzero("rails", rails));
+        Ok(RailFence {
+            rails: rails,
+            offset: offset,
+        })
+    }
+
+    /// Positions before the zigzag repeats.
+    pub fn period(&self) -> usize {
+        if self.rails == 1 { 1 } else { 2 * (self.rails - 1) }
+    }
+
+    fn rail(&self, position: usize) -> usize {
+        let phase = (position + self.offset) % self.period();
+        if phase < self.rails { phase } else { self.period() - phase }
+    }
+
+    fn order(&self, length: usize) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..length).collect();
+        order.sort_by_key(|i| self.rail(*i));
+        order
+    }
+}
+
+impl Cipher for RailFence {
+    fn encrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(gather(message, &self.order(message.len())))
+    }
+
+    fn decrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(scatter(message, &self.order(message.len())))
+    }
+}
+
+/// The message is written along a rod wrapped in a strip of parchment,
+/// `diameter` symbols around, and the strip is read unwound.
+///
+/// That is the same as writing the message in `diameter` rows and
+/// reading it a column at a time.
+#[derive(Debug, Clone, Copy)]
+pub struct Scytale {
+    diameter: usize,
+}
+
+impl Scytale {
+    pub fn new(diameter: usize) -> Result<Scytale> {
+        try!(require_nonzero("diameter", diameter));
+        Ok(Scytale { diameter: diameter })
+    }
+
+    fn order(&self, length: usize) -> Vec<usize> {
+        let columns = (length + self.diameter - 1) / self.diameter;
+        let mut order = Vec::with_capacity(length);
+        for column in 0..columns {
+            for row in 0..self.diameter {
+                let i = row * columns + column;
+                if i < length {
+                    order.push(i);
+                }
+            }
+        }
+        order
+    }
+}
+
+impl Cipher for Scytale {
+    fn encrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(gather(message, &self.order(message.len())))
+    }
+
+    fn decrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(scatter(message, &self.order(message.len())))
+    }
+}
+
+/// Paths a route cipher can take through its grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoutePath {
+    /// Clockwise around the edge from the top left, winding inwards
+    Spiral,
+    /// Down the first column, up the second, down the third...
+    Boustrophedon,
+}
+
+/// The message is written in rows `columns` wide, and read off by
+/// following a path through the grid. Cells the message did not reach
+/// in the last row are stepped over.
+#[derive(Debug, Clone, Copy)]
+pub struct Route {
+    columns: usize,
+    path: RoutePath,
+}
+
+impl Route {
+    pub fn new(columns: usize, path: RoutePath) -> Result<Route> {
+        try!(require_nonzero("columns", columns));
+        Ok(Route {
+            columns: columns,
+            path: path,
+        })
+    }
+
+    fn cells(&self, rows: usize) -> Vec<(usize, usize)> {
+        let columns = self.columns;
+        let mut cells = Vec::with_capacity(rows * columns);
+        match self.path {
+            RoutePath::Boustrophedon => {
+                for column in 0..columns {
+                    for step in 0..rows {
+                        let row = if column % 2 == 0 { step } else { rows - 1 - step };
+                        cells.push((row, column));
+                    }
+                }
+            }
+            RoutePath::Spiral => {
+                let (mut top, mut left) = (0, 0);
+                let (mut bottom, mut right) = (rows, columns);
+                while top < bottom && left < right {
+                    cells.extend((left..right).map(|c| (top, c)));
+                    cells.extend((top + 1..bottom).map(|r| (r, right - 1)));
+                    if bottom - top > 1 {
+                        cells.extend((left..right - 1).rev().map(|c| (bottom - 1, c)));
+                    }
+                    if right - left > 1 {
+                        cells.extend((top + 1..bottom - 1).rev().map(|r| (r, left)));
+                    }
+                    top += 1;
+                    left += 1;
+                    bottom -= 1;
+                    right -= 1;
+                }
+            }
+        }
+        cells
+    }
+
+    fn order(&self, length: usize) -> Vec<usize> {
+        let rows = (length + self.columns - 1) / self.columns;
+        self.cells(rows)
+            .into_iter()
+            .map(|(row, column)| row * self.columns + column)
+            .filter(|i| *i < length)
+            .collect()
+    }
+}
+
+impl Cipher for Route {
+    fn encrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(gather(message, &self.order(message.len())))
+    }
+
+    fn decrypt(&self, _encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        Ok(scatter(message, &self.order(message.len())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::super::{alpha, alphanumeric_space, Error};
+
+    #[test]
+    fn rail_fence_vector() {
+        let e = alpha();
+        let r = RailFence::new(3, 0).unwrap();
+        harness::known_vector(&r,
+                              &e,
+                              "wearediscoveredfleeatonce",
+                              "wecrlteerdsoeefeaocaivden");
+    }
+
+    #[test]
+    fn rail_fence_offset() {
+        let e = alpha();
+        // Starting one step in puts the first symbol on the middle rail.
+        let r = RailFence::new(3, 1).unwrap();
+        harness::known_vector(&r, &e, "abcdefg", "dacegbf");
+        for offset in 0..6 {
+            harness::roundtrip(&RailFence::new(4, offset).unwrap(), &e, "wearediscovered");
+        }
+    }
+
+    #[test]
+    fn scytale_vector() {
+        let e = alpha();
+        let s = Scytale::new(4).unwrap();
+        harness::known_vector(&s, &e, "iamhurtverybadlyhelp", "iryyatbhmvaehedlurlp");
+    }
+
+    #[test]
+    fn route_spiral() {
+        let e = alpha();
+        let r = Route::new(4, RoutePath::Spiral).unwrap();
+        // abcd
+        // efgh
+        // ijkl
+        harness::known_vector(&r, &e, "abcdefghijkl", "abcdhlkjiefg");
+        harness::roundtrip(&r, &e, "abcdefghij");
+    }
+
+    #[test]
+    fn route_boustrophedon() {
+        let e = alpha();
+        let r = Route::new(3, RoutePath::Boustrophedon).unwrap();
+        // abc
+        // def
+        // gh
+        harness::known_vector(&r, &e, "abcdefgh", "adghebcf");
+    }
+
+    #[test]
+    fn custom_alphabet() {
+        let e = alphanumeric_space();
+        harness::roundtrip(&Scytale::new(5).unwrap(), &e, "meet me at the 4th gate at 9");
+        harness::roundtrip(&Route::new(6, RoutePath::Spiral).unwrap(),
+                           &e,
+                           "meet me at the 4th gate at 9");
+    }
+
+    #[test]
+    fn reject_zero_rails() {
+        match RailFence::new(0, 0) {
+            Err(Error::ZeroParameter("rails")) => (),
+            Ok(_) => panic!("We built a fence with no rails!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
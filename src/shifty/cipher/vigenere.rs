@@ -0,0 +1,149 @@
+//! The additive Vigenère cipher, where the key repeats
+//! for as long as the message goes on.
+
+use super::{Cipher, SymbolTransform};
+use super::keystream::{Combine, Keystream, KeystreamState, Progressive, Repeating};
+use super::super::{Action, EncodeNum, Encoding, Result};
+
+/// Shift a single symbol up (encrypt) or down (decrypt) by `key`.
+pub fn transform(message: &EncodeNum, key: &EncodeNum, size: &usize, action: &Action) -> EncodeNum {
+    let s = *size as i64;
+    let m = u64::from(*message) as i64;
+    let k = u64::from(*key) as i64;
+    let offset: i64 = match *action {
+        Action::Encrypt => k,
+        Action::Decrypt => -k,
+    };
+    let c = (m + offset + s) % s;
+    EncodeNum::from(c as u64)
+}
+
+/// `transform` as a `Combine`, for encrypting along any keystream.
+pub fn add(message: &EncodeNum, key: &EncodeNum, size: &usize) -> EncodeNum {
+    transform(message, key, size, &Action::Encrypt)
+}
+
+/// `transform` as a `Combine`, for decrypting along any keystream.
+pub fn subtract(message: &EncodeNum, key: &EncodeNum, size: &usize) -> EncodeNum {
+    transform(message, key, size, &Action::Decrypt)
+}
+
+/// The message plus a keystream, which is the key repeated unless
+/// another `Keystream` is given.
+#[derive(Debug)]
+pub struct Vigenere {
+    keystream: Box<Keystream>,
+}
+
+impl Vigenere {
+    pub fn new(key: Vec<EncodeNum>) -> Result<Vigenere> {
+        Ok(Vigenere::with_keystream(Box::new(try!(Repeating::new(key)))))
+    }
+
+    pub fn from_key(encoding: &Encoding, keytext: &str) -> Result<Vigenere> {
+        Vigenere::new(try!(encoding.vectorize_string(keytext)))
+    }
+
+    pub fn with_keystream(keystream: Box<Keystream>) -> Vigenere {
+        Vigenere { keystream: keystream }
+    }
+
+    /// Trithemius' progressive cipher, shifting each symbol one
+    /// further than the last.
+    pub fn trithemius() -> Vigenere {
+        Vigenere::with_keystream(Box::new(Progressive::trithemius()))
+    }
+}
+
+impl Cipher for Vigenere {
+    fn encrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        super::transform_all(self.symbolwise(encoding, Action::Encrypt).unwrap(), message)
+    }
+
+    fn decrypt(&self, encoding: &Encoding, message: &[EncodeNum]) -> Result<Vec<EncodeNum>> {
+        super::transform_all(self.symbolwise(encoding, Action::Decrypt).unwrap(), message)
+    }
+
+    fn symbolwise(&self, encoding: &Encoding, action: Action) -> Option<Box<SymbolTransform>> {
+        let (combine, name): (Combine, &'static str) = match action {
+            Action::Encrypt => (Box::new(add), "Encrypt"),
+            Action::Decrypt => (Box::new(subtract), "Decrypt"),
+        };
+        Some(Box::new(KeystreamState::new(&*self.keystream, encoding.size(), combine, name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::harness;
+    use super::super::keystream::{Generator, Incrementing};
+    use super::super::super::{alpha, Action, EncodeNum};
+
+    #[test]
+    fn transform_num_simple_add() {
+        let m = EncodeNum::from(2);
+        let k = EncodeNum::from(3);
+        let c = EncodeNum::from(5);
+        let abc_size: usize = 100;
+        assert_eq!(transform(&m, &k, &abc_size, &Action::Encrypt), c)
+    }
+
+    #[test]
+    fn transform_num_simple_sub() {
+        let m = EncodeNum::from(2);
+        let k = EncodeNum::from(3);
+        let c = EncodeNum::from(5);
+        let abc_size: usize = 100;
+        assert_eq!(transform(&c, &k, &abc_size, &Action::Decrypt), m)
+    }
+
+    #[test]
+    fn transform_num_wrapping_add() {
+        let m = EncodeNum::from(2);
+        let k = EncodeNum::from(3);
+        let c = EncodeNum::from(1);
+        let abc_size: usize = 4;
+        assert_eq!(transform(&m, &k, &abc_size, &Action::Encrypt), c)
+    }
+
+    #[test]
+    fn transform_num_wrapping_sub() {
+        let m = EncodeNum::from(2);
+        let k = EncodeNum::from(3);
+        let c = EncodeNum::from(1);
+        let abc_size: usize = 4;
+        assert_eq!(transform(&c, &k, &abc_size, &Action::Decrypt), m)
+    }
+
+    #[test]
+    fn attack_at_dawn() {
+        let e = alpha();
+        let v = Vigenere::from_key(&e, "lemon").unwrap();
+        harness::known_vector(&v, &e, "attackatdawn", "lxfopvefrnhr");
+    }
+
+    #[test]
+    fn trithemius_vector() {
+        let e = alpha();
+        harness::known_vector(&Vigenere::trithemius(), &e, "abcd", "aceg");
+        harness::roundtrip(&Vigenere::trithemius(), &e, "thequickbrownfoxjumpsoverthelazydog");
+    }
+
+    #[test]
+    fn incrementing_key() {
+        let e = alpha();
+        let key = e.vectorize_string("ab").unwrap();
+        let v = Vigenere::with_keystream(Box::new(Incrementing::new(key, 1).unwrap()));
+        harness::known_vector(&v, &e, "aaaaaa", "abbccd");
+    }
+
+    #[test]
+    fn generated_keystream() {
+        let e = alpha();
+        let v = Vigenere::with_keystream(Box::new(Generator::new(|i| {
+            EncodeNum::from(if i % 2 == 0 { 0 } else { 27 })
+        })));
+        harness::known_vector(&v, &e, "aaaa", "abab");
+    }
+}
@@ -3,6 +3,17 @@
 
 #![allow(dead_code)]
 use super::Encoding;
+use super::Result;
+use super::error::ConfigError;
+
+/// Names accepted by `encoding_by_name`.
+pub const BUILTIN_ENCODINGS: [&'static str; 7] = ["alpha",
+                                                 "alpha_space",
+                                                 "alphanumeric",
+                                                 "alphanumeric_space",
+                                                 "playfair",
+                                                 "playfair_6x6",
+                                                 "digits"];
 
 fn add_num(e: &mut Encoding) {
     for i in 0..9 {
@@ -10,6 +21,12 @@ fn add_num(e: &mut Encoding) {
     }
 }
 
+fn add_digits(e: &mut Encoding) {
+    for i in 0..10 {
+        e.insert_char(char_num('0', i));
+    }
+}
+
 fn add_az(e: &mut Encoding) {
     for i in 0..26 {
         e.insert_char(char_num('a', i));
@@ -69,3 +86,83 @@ pub fn alpha_space() -> Encoding {
     map_whitespace(&mut e);
     e
 }
+
+/// The 25 letters of a 5x5 Playfair grid, with J written as I.
+pub fn playfair() -> Encoding {
+    let mut e: Encoding = Encoding::new();
+    for i in 0..26 {
+        let c = char_num('a', i);
+        if c != 'j' {
+            e.insert_char(c);
+        }
+    }
+    map_AZ(&mut e);
+    e.insert_map('j', 'i');
+    e.insert_map('J', 'i');
+    e
+}
+
+/// All 26 letters and all ten digits, filling a 6x6 grid.
+pub fn playfair_6x6() -> Encoding {
+    let mut e: Encoding = Encoding::new();
+    add_az(&mut e);
+    map_AZ(&mut e);
+    add_digits(&mut e);
+    e
+}
+
+/// The ten digits, for ciphers that write letters as numbers.
+pub fn digits() -> Encoding {
+    let mut e: Encoding = Encoding::new();
+    add_digits(&mut e);
+    e
+}
+
+/// Look up one of the built-in encodings by name.
+pub fn encoding_by_name(name: &str) -> Result<Encoding> {
+    match name {
+        "alpha" => Ok(alpha()),
+        "alpha_space" => Ok(alpha_space()),
+        "alphanumeric" => Ok(alphanumeric()),
+        "alphanumeric_space" => Ok(alphanumeric_space()),
+        "playfair" => Ok(playfair()),
+        "playfair_6x6" => Ok(playfair_6x6()),
+        "digits" => Ok(digits()),
+        _ => {
+            Err(From::from(ConfigError::SchemaError(format!("Unknown encoding '{}', expected \
+                                                             one of {:?}",
+                                                            name,
+                                                            BUILTIN_ENCODINGS))))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Error;
+    use super::super::error::ConfigError;
+
+    #[test]
+    fn every_builtin_name_resolves() {
+        for name in BUILTIN_ENCODINGS.iter() {
+            assert!(encoding_by_name(name).is_ok());
+        }
+    }
+
+    #[test]
+    fn playfair_sizes() {
+        assert_eq!(playfair().size(), 25);
+        assert_eq!(playfair().map_filter_string("Jumbo"), "iumbo");
+        assert_eq!(playfair_6x6().size(), 36);
+    }
+
+    #[test]
+    fn unknown_name_is_schema_error() {
+        match encoding_by_name("klingon") {
+            Err(Error::InvalidConfig(ConfigError::SchemaError(_))) => (),
+            Ok(_) => panic!("We found an encoding that does not exist!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+}
@@ -1,24 +1,71 @@
 use std::collections::HashMap;
 use std::fmt;
+use std::io::{BufWriter, Read, Write};
 use itertools::Itertools;
 use super::Result;
 use super::Error;
+use super::error::{Position, Source};
 use super::parser;
+use super::modular;
+use super::stream;
+use super::cipher::{Cipher, Vigenere};
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 /// Selects if shifting should be up (encrypted) or down (decrypted)
 pub enum Action {
     Encrypt,
     Decrypt,
 }
 
+#[derive(Debug, Clone, Copy)]
+/// Selects what happens to characters which are not in the alphabet
+pub enum UnknownChars {
+    /// Stop with `Error::CharNotInEncoding`
+    Fail,
+    /// Read everything, then fail with `Error::CharsNotInEncoding`
+    /// listing every char that was not in the alphabet
+    Report,
+    /// Apply the encoding's mapping, then drop anything still unknown
+    Drop,
+    /// Apply the encoding's mapping, then copy anything still unknown
+    /// straight to the output, restoring the case of mapped characters.
+    /// With `advance_key` each copied character uses up a key position,
+    /// which ciphers that need the whole message can not do.
+    Passthrough { advance_key: bool },
+}
+
+impl UnknownChars {
+    /// Whether characters go through the encoding's mapping first.
+    fn maps(&self) -> bool {
+        match *self {
+            UnknownChars::Fail | UnknownChars::Report => false,
+            UnknownChars::Drop | UnknownChars::Passthrough { .. } => true,
+        }
+    }
+}
+
+/// Where a symbol goes in the output of a cipher that needs the
+/// whole message, so passthrough characters land back in place.
+enum Slot {
+    Symbol { upper: bool },
+    Literal(char),
+}
+
 custom_derive! {
     #[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Debug, Hash)]
-    #[derive(NewtypeFrom, NewtypeAdd, NewtypeSub, NewtypeRem)]
+    #[derive(NewtypeFrom, NewtypeAdd, NewtypeSub, NewtypeMul, NewtypeRem)]
     pub struct EncodeNum(u64);
 }
 
+impl EncodeNum {
+    /// The number which multiplies with this one to give 1 mod `modulus`,
+    /// if there is one.
+    pub fn inverse_mod(&self, modulus: u64) -> Option<EncodeNum> {
+        modular::inverse(self.0, modulus).map(EncodeNum)
+    }
+}
+
 impl fmt::Display for EncodeNum {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.0)
@@ -26,19 +73,26 @@ impl fmt::Display for EncodeNum {
 }
 
 
-fn transform(message: &EncodeNum, key: &EncodeNum, size: &usize, action: &Action) -> EncodeNum {
-    let s = *size as i32;
-    let m = message.0 as i32;
-    let k = key.0 as i32;
-    let offset: i32 = match *action {
-        Action::Encrypt => k,
-        Action::Decrypt => -k,
-    };
-    let c = (m + offset + s) % s;
-    EncodeNum(c as u64)
+/// A key after being checked against an `Encoding`.
+#[derive(Debug)]
+pub struct KeyCheck {
+    /// The key, with anything outside the alphabet dropped
+    pub key: String,
+    /// Characters that were dropped from the key, in the order first seen
+    pub dropped: Vec<char>,
 }
 
-
+fn write_char<W: Write>(output: &mut W, c: char, upper: bool) -> Result<()> {
+    let mut utf8 = [0; 4];
+    if upper {
+        for u in c.to_uppercase() {
+            try!(output.write_all(u.encode_utf8(&mut utf8).as_bytes()));
+        }
+    } else {
+        try!(output.write_all(c.encode_utf8(&mut utf8).as_bytes()));
+    }
+    Ok(())
+}
 
 #[derive(Debug)]
 pub struct Encoding {
@@ -76,18 +130,23 @@ impl Encoding {
         trace!("Map {:?} -> {:?}", x, y);
     }
 
+    /// Number of characters in the alphabet.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
     fn char_in_working_set(&self, c: &char) -> bool {
         self.char_number_map.contains_key(c)
     }
 
-    fn char_to_number(&self, c: &char) -> Result<EncodeNum> {
+    pub fn char_to_number(&self, c: &char) -> Result<EncodeNum> {
         match self.char_number_map.get(c) {
             Some(n) => Ok(*n),
-            None => Err(Error::CharNotInEncoding(*c)),
+            None => Err(Error::CharNotInEncoding(*c, None)),
         }
     }
 
-    fn number_to_char(&self, n: &EncodeNum) -> Result<char> {
+    pub fn number_to_char(&self, n: &EncodeNum) -> Result<char> {
         match self.number_char_map.get(n) {
             Some(c) => Ok(*c),
             None => Err(Error::NumberNotInEncoding(*n)),
@@ -98,7 +157,7 @@ impl Encoding {
         *self.char_char_map.get(c).unwrap_or(c)
     }
 
-    fn vectorize_string(&self, s: &str) -> Result<Vec<EncodeNum>> {
+    pub fn vectorize_string(&self, s: &str) -> Result<Vec<EncodeNum>> {
         s.chars()
             .map(|c| self.char_to_number(&c))
             .collect()
@@ -117,24 +176,212 @@ impl Encoding {
                              keytext: &str,
                              action: Action)
                              -> Result<String> {
-        let key: Vec<EncodeNum> = try!(self.vectorize_string(keytext));
-        let keysize = key.len();
-        let transformed_chars: Vec<char> = try!(message.chars()
-            .enumerate()
-            .map(|(i, c)| {
-                let message_num: EncodeNum = try!(self.char_to_number(&c));
-                let key_num: EncodeNum = key[i % keysize];
-                let cipher_num = transform(&message_num, &key_num, &self.size, &action);
-                trace!("{:?} m({}) k({}) -> c({})",
-                       action,
-                       message_num,
-                       key_num,
-                       cipher_num);
-                self.number_to_char(&cipher_num)
-            })
-            .collect());
-        let new_message: String = transformed_chars.iter().map(|c| *c).collect();
-        Ok(new_message)
+        let cipher = try!(Vigenere::from_key(self, keytext));
+        self.transform_with(message, &cipher, action)
+    }
+
+    /// Transform `message` with any `Cipher`.
+    pub fn transform_with(&self, message: &str, cipher: &Cipher, action: Action) -> Result<String> {
+        let mut output: Vec<u8> = Vec::with_capacity(message.len());
+        try!(self.transform_stream(message.as_bytes(),
+                                   &mut output,
+                                   cipher,
+                                   action,
+                                   UnknownChars::Fail));
+        Ok(String::from_utf8(output).expect("transform_stream wrote invalid UTF-8"))
+    }
+
+    /// Transform everything `input` has to offer, writing it to `output`.
+    ///
+    /// Ciphers that work a symbol at a time are fed as we read, with their
+    /// key position carried from one buffer to the next, so this uses the
+    /// same memory no matter how large the message is. Other ciphers need
+    /// the whole message, so it is read in before being transformed.
+    pub fn transform_stream<R: Read, W: Write>(&self,
+                                               input: R,
+                                               output: W,
+                                               cipher: &Cipher,
+                                               action: Action,
+                                               unknown: UnknownChars)
+                                               -> Result<()> {
+        self.transform_stream_into(self, input, output, cipher, action, unknown)
+    }
+
+    /// Like `transform_stream`, but the output is written with the
+    /// symbols of `to`, for ciphers such as the straddling checkerboard
+    /// that turn one alphabet into another. Unknown characters are read
+    /// with this encoding and copied through unchanged.
+    pub fn transform_stream_into<R: Read, W: Write>(&self,
+                                                    to: &Encoding,
+                                                    input: R,
+                                                    output: W,
+                                                    cipher: &Cipher,
+                                                    action: Action,
+                                                    unknown: UnknownChars)
+                                                    -> Result<()> {
+        let mut output = BufWriter::new(output);
+        let mut state = cipher.symbolwise(self, action);
+        // Ciphers that need the whole message have no key positions to
+        // hand out one at a time.
+        if let (None, UnknownChars::Passthrough { advance_key: true }) = (state.as_ref(), unknown) {
+            return Err(Error::CannotAdvanceKey);
+        }
+        let mut buffered: Vec<EncodeNum> = Vec::new();
+        let mut layout: Vec<Slot> = Vec::new();
+        let mut position = Position::start(Source::Message);
+        let mut not_found: Vec<(char, Position)> = Vec::new();
+        let restore_case = match unknown {
+            UnknownChars::Passthrough { .. } => true,
+            _ => false,
+        };
+
+        for c in stream::Chars::new(input) {
+            let original = try!(c);
+            let here = position;
+            position.advance(original);
+
+            let c = if unknown.maps() {
+                self.map_char(&original)
+            } else {
+                original
+            };
+            if !self.char_in_working_set(&c) {
+                match unknown {
+                    UnknownChars::Fail => return Err(Error::CharNotInEncoding(c, Some(here))),
+                    UnknownChars::Report => not_found.push((c, here)),
+                    UnknownChars::Drop => (),
+                    UnknownChars::Passthrough { advance_key } => {
+                        match state {
+                            Some(ref mut state) => {
+                                if advance_key {
+                                    try!(state.skip());
+                                }
+                                try!(write_char(&mut output, original, false));
+                            }
+                            None => layout.push(Slot::Literal(original)),
+                        }
+                    }
+                }
+                continue;
+            }
+            if !not_found.is_empty() {
+                // The output is wrong from here on, so only keep looking.
+                continue;
+            }
+
+            let upper = restore_case && original != c && original.is_uppercase();
+            let message_num: EncodeNum = try!(self.char_to_number(&c));
+            match state {
+                Some(ref mut state) => {
+                    let cipher_num = try!(state.transform(message_num));
+                    try!(to.write_number(&mut output, &cipher_num, upper));
+                }
+                None => {
+                    buffered.push(message_num);
+                    layout.push(Slot::Symbol { upper: upper });
+                }
+            }
+        }
+
+        if !not_found.is_empty() {
+            return Err(Error::CharsNotInEncoding(not_found));
+        }
+
+        if state.is_none() {
+            let transformed = match action {
+                Action::Encrypt => try!(cipher.encrypt(self, &buffered)),
+                Action::Decrypt => try!(cipher.decrypt(self, &buffered)),
+            };
+
+            // The cipher may have padded or trimmed the message, so extra
+            // symbols go on the end and missing ones just leave a gap.
+            let mut transformed = transformed.iter();
+            for slot in &layout {
+                match *slot {
+                    Slot::Literal(c) => try!(write_char(&mut output, c, false)),
+                    Slot::Symbol { upper } => {
+                        if let Some(n) = transformed.next() {
+                            try!(to.write_number(&mut output, n, upper));
+                        }
+                    }
+                }
+            }
+            for n in transformed {
+                try!(to.write_number(&mut output, n, false));
+            }
+        }
+        try!(output.flush());
+        Ok(())
+    }
+
+    fn write_number<W: Write>(&self, output: &mut W, n: &EncodeNum, upper: bool) -> Result<()> {
+        let c = try!(self.number_to_char(n));
+        write_char(output, c, upper)
+    }
+
+    /// The alphabet reordered to start with the distinct characters of
+    /// `keyword`, followed by the rest in their usual order.
+    pub fn keyed_alphabet(&self, keyword: &str) -> Result<Vec<EncodeNum>> {
+        let mut alphabet: Vec<EncodeNum> = Vec::with_capacity(self.size);
+        for n in try!(self.vectorize_string(keyword)) {
+            if !alphabet.contains(&n) {
+                alphabet.push(n);
+            }
+        }
+        for i in 0..self.size {
+            let n = EncodeNum(i as u64);
+            if !alphabet.contains(&n) {
+                alphabet.push(n);
+            }
+        }
+        Ok(alphabet)
+    }
+
+    /// Check that `keytext` leaves something to shift by.
+    ///
+    /// With `UnknownChars::Fail` the first character outside the alphabet
+    /// is an error, and `UnknownChars::Report` lists all of them.
+    /// Otherwise the key is mapped and filtered like a message,
+    /// and the characters that did not survive are reported.
+    pub fn check_key(&self, keytext: &str, unknown: UnknownChars) -> Result<KeyCheck> {
+        let mut key = String::with_capacity(keytext.len());
+        let mut dropped: Vec<char> = Vec::new();
+        let mut position = Position::start(Source::Key);
+        let mut not_found: Vec<(char, Position)> = Vec::new();
+        for c in keytext.chars() {
+            let here = position;
+            position.advance(c);
+            let mapped = if unknown.maps() {
+                self.map_char(&c)
+            } else {
+                c
+            };
+            if self.char_in_working_set(&mapped) {
+                key.push(mapped);
+                continue;
+            }
+            match unknown {
+                UnknownChars::Fail => return Err(Error::CharNotInEncoding(c, Some(here))),
+                UnknownChars::Report => not_found.push((c, here)),
+                UnknownChars::Drop |
+                UnknownChars::Passthrough { .. } => {
+                    if !dropped.contains(&c) {
+                        dropped.push(c);
+                    }
+                }
+            }
+        }
+
+        if !not_found.is_empty() {
+            return Err(Error::CharsNotInEncoding(not_found));
+        }
+        if key.is_empty() {
+            return Err(Error::EmptyKey(dropped));
+        }
+        Ok(KeyCheck {
+            key: key,
+            dropped: dropped,
+        })
     }
 
     pub fn map_string(&self, s: &str) -> String {
@@ -207,43 +454,6 @@ fn decode() {
     assert_eq!(e.number_to_char(&EncodeNum(0)).unwrap(), 'a')
 }
 
-#[test]
-fn transform_num_simple_add() {
-    let m = EncodeNum(2);
-    let k = EncodeNum(3);
-    let c = EncodeNum(5);
-    let abc_size: usize = 100;
-    assert_eq!(transform(&m, &k, &abc_size, &Action::Encrypt), c)
-}
-
-#[test]
-fn transform_num_simple_sub() {
-    let m = EncodeNum(2);
-    let k = EncodeNum(3);
-    let c = EncodeNum(5);
-    let abc_size: usize = 100;
-    assert_eq!(transform(&c, &k, &abc_size, &Action::Decrypt), m)
-}
-
-
-#[test]
-fn transform_num_wrapping_add() {
-    let m = EncodeNum(2);
-    let k = EncodeNum(3);
-    let c = EncodeNum(1);
-    let abc_size: usize = 4;
-    assert_eq!(transform(&m, &k, &abc_size, &Action::Encrypt), c)
-}
-
-#[test]
-fn transform_num_wrapping_sub() {
-    let m = EncodeNum(2);
-    let k = EncodeNum(3);
-    let c = EncodeNum(1);
-    let abc_size: usize = 4;
-    assert_eq!(transform(&c, &k, &abc_size, &Action::Decrypt), m)
-}
-
 #[test]
 fn transform_char_identity() {
     let mut e = Encoding::new();
@@ -289,6 +499,169 @@ fn encrypt_decrypt() {
     assert_eq!(e.decrypt(&c, &k).unwrap(), m);
 }
 
+#[test]
+fn transform_stream_matches_message() {
+    let e = super::alphanumeric_space();
+    let m = "the quick brown fox jumps over the lazy dog";
+    let k = "lemon";
+    let v = Vigenere::from_key(&e, k).unwrap();
+    let mut c: Vec<u8> = Vec::new();
+    e.transform_stream(m.as_bytes(), &mut c, &v, Action::Encrypt, UnknownChars::Fail).unwrap();
+    assert_eq!(String::from_utf8(c).unwrap(), e.encrypt(m, k).unwrap());
+}
+
+#[test]
+fn transform_stream_drops_unknown_chars() {
+    let e = super::alpha();
+    let v = Vigenere::from_key(&e, "b").unwrap();
+    let mut c: Vec<u8> = Vec::new();
+    e.transform_stream("A-b!".as_bytes(), &mut c, &v, Action::Encrypt, UnknownChars::Drop)
+        .unwrap();
+    assert_eq!(String::from_utf8(c).unwrap(), "bc");
+}
+
+#[test]
+fn empty_key_is_an_error() {
+    let e = super::alpha();
+    match e.encrypt("abc", "") {
+        Err(Error::EmptyKey(ref dropped)) if dropped.is_empty() => (),
+        Ok(_) => panic!("We encrypted with an empty key!"),
+        Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+    }
+}
+
+#[test]
+fn check_key_reports_dropped_chars() {
+    let e = super::alpha();
+    let check = e.check_key("Se-cr-et!", UnknownChars::Drop).unwrap();
+    assert_eq!(check.key, "secret");
+    assert_eq!(check.dropped, vec!['-', '!']);
+}
+
+#[test]
+fn check_key_filtered_to_nothing() {
+    let e = super::alpha();
+    match e.check_key("123 !", UnknownChars::Drop) {
+        Err(Error::EmptyKey(dropped)) => assert_eq!(dropped, vec!['1', '2', '3', ' ', '!']),
+        Ok(_) => panic!("We accepted a key with nothing in it!"),
+        Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+    }
+}
+
+#[test]
+fn check_key_strict() {
+    let e = super::alpha();
+    match e.check_key("Secret", UnknownChars::Fail) {
+        Err(Error::CharNotInEncoding('S', Some(position))) => {
+            assert_eq!(position, Position::start(Source::Key))
+        }
+        Ok(_) => panic!("We accepted a key with an unknown char!"),
+        Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+    }
+}
+
+#[test]
+fn strict_error_has_position() {
+    let e = super::alpha();
+    let v = Vigenere::from_key(&e, "b").unwrap();
+    let m = "ab\nñc!".as_bytes();
+    let mut c: Vec<u8> = Vec::new();
+    match e.transform_stream(m, &mut c, &v, Action::Encrypt, UnknownChars::Fail) {
+        Err(Error::CharNotInEncoding('\n', Some(position))) => {
+            assert_eq!(position,
+                       Position {
+                           source: Source::Message,
+                           offset: 2,
+                           line: 1,
+                           column: 3,
+                       })
+        }
+        Ok(_) => panic!("We encrypted an unknown char!"),
+        Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+    }
+}
+
+#[test]
+fn report_every_unknown_char() {
+    let e = super::alpha();
+    let v = Vigenere::from_key(&e, "b").unwrap();
+    let m = "ab\nñc!".as_bytes();
+    let mut c: Vec<u8> = Vec::new();
+    match e.transform_stream(m, &mut c, &v, Action::Encrypt, UnknownChars::Report) {
+        Err(Error::CharsNotInEncoding(found)) => {
+            let summary: Vec<(char, usize, usize, usize)> = found.iter()
+                .map(|&(c, p)| (c, p.offset, p.line, p.column))
+                .collect();
+            assert_eq!(summary,
+                       vec![('\n', 2, 1, 3), ('ñ', 3, 2, 1), ('!', 6, 2, 3)]);
+        }
+        Ok(_) => panic!("We encrypted unknown chars!"),
+        Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+    }
+}
+
+#[cfg(test)]
+fn passthrough(e: &Encoding, cipher: &Cipher, m: &str, action: Action, advance: bool) -> String {
+    let mut c: Vec<u8> = Vec::new();
+    let unknown = UnknownChars::Passthrough { advance_key: advance };
+    e.transform_stream(m.as_bytes(), &mut c, cipher, action, unknown).unwrap();
+    String::from_utf8(c).unwrap()
+}
+
+#[test]
+fn passthrough_keeps_layout() {
+    let e = super::alpha();
+    let v = Vigenere::from_key(&e, "lemon").unwrap();
+    let m = "Attack at Dawn!";
+    let c = passthrough(&e, &v, m, Action::Encrypt, false);
+    assert_eq!(c, "Lxfopv ef Rnhr!");
+    assert_eq!(passthrough(&e, &v, &c, Action::Decrypt, false), m);
+}
+
+#[test]
+fn passthrough_advances_key() {
+    let e = super::alpha();
+    let v = Vigenere::from_key(&e, "ab").unwrap();
+    assert_eq!(passthrough(&e, &v, "a a", Action::Encrypt, false), "a b");
+    assert_eq!(passthrough(&e, &v, "a a", Action::Encrypt, true), "a a");
+}
+
+#[test]
+fn advance_key_needs_symbolwise_cipher() {
+    let e = super::alpha();
+    let columnar = super::cipher::Columnar::new(&e, "zebras", "").unwrap();
+    let unknown = UnknownChars::Passthrough { advance_key: true };
+    let mut c: Vec<u8> = Vec::new();
+    match e.transform_stream("a a".as_bytes(), &mut c, &columnar, Action::Encrypt, unknown) {
+        Err(Error::CannotAdvanceKey) => (),
+        Ok(_) => panic!("We advanced the key of a cipher that has none!"),
+        Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+    }
+}
+
+#[test]
+fn transform_into_digits() {
+    let (e, d) = (super::alpha(), super::digits());
+    let board = super::cipher::StraddlingCheckerboard::new(&e, "", &d, "26").unwrap();
+    let (drop, mut c): (UnknownChars, Vec<u8>) = (UnknownChars::Drop, Vec::new());
+    e.transform_stream_into(&d, "Tea".as_bytes(), &mut c, &board, Action::Encrypt, drop).unwrap();
+    assert_eq!(c, b"6150");
+    let mut m: Vec<u8> = Vec::new();
+    d.transform_stream_into(&e, &c[..], &mut m, &board, Action::Decrypt, drop).unwrap();
+    assert_eq!(m, b"tea");
+}
+
+#[test]
+fn keyed_alphabet() {
+    let e = super::alpha();
+    let alphabet: String = e.keyed_alphabet("zebras")
+        .unwrap()
+        .iter()
+        .map(|n| e.number_to_char(n).unwrap())
+        .collect();
+    assert_eq!(alphabet, "zebrascdfghijklmnopqtuvwxy");
+}
+
 #[test]
 fn map_string() {
     let mut e = Encoding::new();
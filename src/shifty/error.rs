@@ -1,13 +1,126 @@
 use std::error;
 use std::fmt;
+use std::io;
+
+/// Which piece of text a character came from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Source {
+    Key,
+    Message,
+}
+
+/// Where a character sits in its text.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Position {
+    pub source: Source,
+    /// Bytes from the start of the text
+    pub offset: usize,
+    /// Line number, starting from 1
+    pub line: usize,
+    /// Chars from the start of the line, starting from 1
+    pub column: usize,
+}
+
+impl Position {
+    pub fn start(source: Source) -> Position {
+        Position {
+            source: source,
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    /// Move past `c` to where the next char will be.
+    pub fn advance(&mut self, c: char) {
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let source = match self.source {
+            Source::Key => "key",
+            Source::Message => "message",
+        };
+        write!(f,
+               "{} line {}, column {} (byte {})",
+               source,
+               self.line,
+               self.column,
+               self.offset)
+    }
+}
 
 /// Error type for dealing with problems
 /// with the encoder.
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Debug)]
 pub enum Error {
     InvalidConfig(ConfigError),
-    CharNotInEncoding(char),
+    /// A char outside the alphabet, and where it was found if we know.
+    CharNotInEncoding(char, Option<Position>),
+    /// Every char outside the alphabet, when asked to collect them all.
+    CharsNotInEncoding(Vec<(char, Position)>),
     NumberNotInEncoding(super::encoding::EncodeNum),
+    /// The encoding has no characters, so there is nothing to work with.
+    EmptyEncoding,
+    /// The key has no characters left to shift by, listing any
+    /// that were dropped for not being in the encoding.
+    EmptyKey(Vec<char>),
+    /// A running key ran out after this many symbols.
+    KeyExhausted(usize),
+    /// Copied characters were meant to use up key positions, but the
+    /// cipher has no key position to give up for them.
+    CannotAdvanceKey,
+    /// A key value shares a factor with the alphabet size, so it has
+    /// no inverse and its ciphertext could not be decrypted.
+    NotCoprime(u64, usize),
+    /// A key meant to reorder the alphabet does not use every character
+    /// exactly once. Lists the missing characters, then the repeated ones.
+    NotAPermutation(Vec<char>, Vec<char>),
+    /// A grid cipher was given an alphabet whose size is not a square.
+    NotASquare(usize),
+    /// Trifid was given an alphabet whose size is not a cube.
+    NotACube(usize),
+    /// A grid this many rows high was labelled with this many
+    /// distinct coordinate characters.
+    WrongCoordinates(usize, usize),
+    /// Ciphertext that should be made of coordinates had another character.
+    NotACoordinate(char),
+    /// A message of this many symbols does not split into whole
+    /// blocks of the second size.
+    NotWholeBlocks(usize, usize),
+    /// A matrix key has this determinant, which has no inverse for
+    /// an alphabet of this size.
+    SingularMatrix(u64, usize),
+    /// A cipher that pairs up the alphabet was given an odd number of characters.
+    OddAlphabet(usize),
+    /// A numeric key had a character that is not a digit, or is a digit
+    /// too large for an alphabet of this size.
+    InvalidDigit(char, usize),
+    /// A cipher parameter that has to be at least one was zero.
+    ZeroParameter(&'static str),
+    /// An alphabet of this many symbols does not fit in a checkerboard
+    /// with this many cells.
+    TooManySymbols(usize, usize),
+    /// A digit encoding of this many symbols has no digit for the last
+    /// coordinate of a grid this many rows high.
+    TooFewDigits(usize, usize),
+    /// Digits that do not stand for any symbol of the alphabet.
+    NotACode(Vec<super::encoding::EncodeNum>),
+    /// An Enigma reflector that leaves this character alone, or does
+    /// not send the character it is swapped with back to it.
+    NotAReflector(char),
+    /// A plugboard cable that does not join two distinct characters
+    /// free of any other cable.
+    InvalidPlug(String),
+    Io(io::Error),
 }
 
 impl From<ConfigError> for Error {
@@ -16,12 +129,117 @@ impl From<ConfigError> for Error {
     }
 }
 
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Error::InvalidConfig(ref err) => write!(f, "{}", err),
-            Error::CharNotInEncoding(c) => write!(f, "Char {:?} not in encoding", c),
+            Error::CharNotInEncoding(c, None) => write!(f, "Char {:?} not in encoding", c),
+            Error::CharNotInEncoding(c, Some(position)) => {
+                write!(f, "Char {:?} not in encoding at {}", c, position)
+            }
+            Error::CharsNotInEncoding(ref found) => {
+                try!(write!(f, "{} chars not in encoding:", found.len()));
+                for &(c, position) in found {
+                    try!(write!(f, "\n  {:?} at {}", c, position));
+                }
+                Ok(())
+            }
             Error::NumberNotInEncoding(n) => write!(f, "Number {:?} not in encoding", n),
+            Error::EmptyEncoding => write!(f, "Encoding has no characters"),
+            Error::EmptyKey(ref dropped) if dropped.is_empty() => write!(f, "Key is empty"),
+            Error::EmptyKey(ref dropped) => {
+                write!(f,
+                       "Key is empty once characters not in the encoding are dropped: {:?}",
+                       dropped)
+            }
+            Error::KeyExhausted(n) => write!(f, "Key ran out after {} characters", n),
+            Error::CannotAdvanceKey => {
+                write!(f, "This cipher can not advance its key past copied characters")
+            }
+            Error::NotCoprime(value, size) => {
+                write!(f,
+                       "{} has no inverse for an alphabet of {} characters",
+                       value,
+                       size)
+            }
+            Error::NotAPermutation(ref missing, ref repeated) => {
+                write!(f,
+                       "Key is not a permutation of the alphabet, missing {:?} and repeating {:?}",
+                       missing,
+                       repeated)
+            }
+            Error::NotASquare(size) => {
+                write!(f,
+                       "Alphabet of {} characters does not fill a square grid",
+                       size)
+            }
+            Error::NotACube(size) => {
+                write!(f, "Alphabet of {} characters does not fill a cube", size)
+            }
+            Error::WrongCoordinates(side, given) => {
+                write!(f,
+                       "Grid of {} rows needs {} distinct coordinate characters, not {}",
+                       side,
+                       side,
+                       given)
+            }
+            Error::NotACoordinate(c) => write!(f, "Char {:?} is not a coordinate", c),
+            Error::NotWholeBlocks(length, block) => {
+                write!(f,
+                       "Message of {} symbols does not split into blocks of {}",
+                       length,
+                       block)
+            }
+            Error::SingularMatrix(determinant, size) => {
+                write!(f,
+                       "Matrix has determinant {}, which has no inverse for an alphabet of {} \
+                        characters",
+                       determinant,
+                       size)
+            }
+            Error::OddAlphabet(size) => {
+                write!(f, "Alphabet of {} characters can not be split into pairs", size)
+            }
+            Error::InvalidDigit(c, size) => {
+                write!(f,
+                       "Key char {:?} is not a digit below the alphabet size of {}",
+                       c,
+                       size)
+            }
+            Error::ZeroParameter(name) => write!(f, "Number of {} can not be zero", name),
+            Error::TooManySymbols(size, cells) => {
+                write!(f,
+                       "Alphabet of {} characters does not fit in a checkerboard of {} cells",
+                       size,
+                       cells)
+            }
+            Error::TooFewDigits(size, side) => {
+                write!(f,
+                       "Digit encoding of {} characters can not write the coordinates of a \
+                        grid of {} rows",
+                       size,
+                       side)
+            }
+            Error::NotACode(ref digits) => {
+                try!(write!(f, "Digits "));
+                for d in digits {
+                    try!(write!(f, "{}", d));
+                }
+                write!(f, " do not stand for any character")
+            }
+            Error::NotAReflector(c) => {
+                write!(f, "Reflector does not swap char {:?} with another char", c)
+            }
+            Error::InvalidPlug(ref pair) => {
+                write!(f, "Plugboard cable {:?} does not join two free chars", pair)
+            }
+            Error::Io(ref err) => write!(f, "{}", err),
         }
     }
 }
@@ -30,16 +248,60 @@ impl error::Error for Error {
     fn description(&self) -> &str {
         match *self {
             Error::InvalidConfig(_) => "could not parse config as Encoding",
-            Error::CharNotInEncoding(_) => "found char not in encoding",
+            Error::CharNotInEncoding(..) => "found char not in encoding",
+            Error::CharsNotInEncoding(_) => "found chars not in encoding",
             Error::NumberNotInEncoding(_) => "found number not in encoding",
+            Error::EmptyEncoding => "encoding has no characters",
+            Error::EmptyKey(_) => "key has no characters in the encoding",
+            Error::KeyExhausted(_) => "key is shorter than the message",
+            Error::CannotAdvanceKey => "cipher can not advance its key past a copied char",
+            Error::NotCoprime(..) => "key is not coprime to the alphabet size",
+            Error::NotAPermutation(..) => "key is not a permutation of the alphabet",
+            Error::NotASquare(_) => "alphabet size is not a square",
+            Error::NotACube(_) => "alphabet size is not a cube",
+            Error::WrongCoordinates(..) => "wrong number of coordinate characters",
+            Error::NotACoordinate(_) => "found char that is not a coordinate",
+            Error::NotWholeBlocks(..) => "message does not split into whole blocks",
+            Error::SingularMatrix(..) => "matrix has no inverse modulo the alphabet size",
+            Error::OddAlphabet(_) => "alphabet size is odd",
+            Error::InvalidDigit(..) => "key is not made of digits below the alphabet size",
+            Error::ZeroParameter(_) => "cipher parameter was zero",
+            Error::TooManySymbols(..) => "alphabet does not fit in the checkerboard",
+            Error::TooFewDigits(..) => "digit encoding is too small for the grid",
+            Error::NotACode(_) => "found digits that do not stand for any character",
+            Error::NotAReflector(_) => "reflector does not swap characters in pairs",
+            Error::InvalidPlug(_) => "plugboard cable does not join two free characters",
+            Error::Io(_) => "could not read or write message",
         }
     }
 
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             Error::InvalidConfig(ref e) => Some(e),
-            Error::CharNotInEncoding(_) => None,
+            Error::CharNotInEncoding(..) => None,
+            Error::CharsNotInEncoding(_) => None,
             Error::NumberNotInEncoding(_) => None,
+            Error::EmptyEncoding => None,
+            Error::EmptyKey(_) => None,
+            Error::KeyExhausted(_) => None,
+            Error::CannotAdvanceKey => None,
+            Error::NotCoprime(..) => None,
+            Error::NotAPermutation(..) => None,
+            Error::NotASquare(_) => None,
+            Error::NotACube(_) => None,
+            Error::WrongCoordinates(..) => None,
+            Error::NotACoordinate(_) => None,
+            Error::NotWholeBlocks(..) => None,
+            Error::SingularMatrix(..) => None,
+            Error::OddAlphabet(_) => None,
+            Error::InvalidDigit(..) => None,
+            Error::ZeroParameter(_) => None,
+            Error::TooManySymbols(..) => None,
+            Error::TooFewDigits(..) => None,
+            Error::NotACode(_) => None,
+            Error::NotAReflector(_) => None,
+            Error::InvalidPlug(_) => None,
+            Error::Io(ref e) => Some(e),
         }
     }
 }
@@ -5,9 +5,15 @@ mod error;
 mod encoding;
 mod parser;
 mod encoders;
+mod modular;
+pub mod stream;
+pub mod cipher;
+pub mod analysis;
 
 pub use self::encoding::Encoding;
 pub use self::encoding::Action;
+pub use self::encoding::EncodeNum;
+pub use self::encoding::UnknownChars;
 pub use self::error::Error;
 pub type Result<T> = std::result::Result<T, self::error::Error>;
 
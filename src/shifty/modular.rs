@@ -0,0 +1,180 @@
+//! Arithmetic modulo the size of an alphabet.
+
+/// Greatest common divisor of `a` and `b`.
+pub fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// The `x` for which `a * x = 1 (mod modulus)`, if there is one.
+pub fn inverse(a: u64, modulus: u64) -> Option<u64> {
+    if modulus == 0 {
+        return None;
+    }
+    // Extended Euclid, tracking only the coefficient of `a`.
+    let (mut old_r, mut r) = ((a % modulus) as i64, modulus as i64);
+    let (mut old_s, mut s) = (1i64, 0i64);
+    while r != 0 {
+        let q = old_r / r;
+        let next_r = old_r - q * r;
+        old_r = r;
+        r = next_r;
+        let next_s = old_s - q * s;
+        old_s = s;
+        s = next_s;
+    }
+    if old_r != 1 {
+        return None;
+    }
+    let m = modulus as i64;
+    Some((((old_s % m) + m) % m) as u64)
+}
+
+/// Square matrix, stored as a list of rows.
+pub type Matrix = Vec<Vec<u64>>;
+
+/// Bring the first `columns` columns of `rows` to upper triangular form
+/// using only row swaps and adding multiples of one row to another, so
+/// it works for any modulus, prime or not. Each column is cleared below
+/// the diagonal by running Euclid's algorithm down it, which leaves the
+/// gcd of the column on the diagonal.
+///
+/// Returns whether an odd number of rows were swapped.
+pub fn triangulate(rows: &mut Vec<Vec<u64>>, columns: usize, modulus: u64) -> bool {
+    let mut flipped = false;
+    for c in 0..columns {
+        if c >= rows.len() {
+            break;
+        }
+        for r in c + 1..rows.len() {
+            while rows[r][c] != 0 {
+                let q = rows[c][c] / rows[r][c];
+                for i in 0..rows[c].len() {
+                    let sub = q * rows[r][i] % modulus;
+                    rows[c][i] = (rows[c][i] + modulus - sub) % modulus;
+                }
+                rows.swap(c, r);
+                flipped = !flipped;
+            }
+        }
+    }
+    flipped
+}
+
+/// Determinant of `matrix`, modulo `modulus`.
+pub fn determinant(matrix: &Matrix, modulus: u64) -> u64 {
+    let mut rows = matrix.clone();
+    let flipped = triangulate(&mut rows, matrix.len(), modulus);
+    let product = (0..rows.len()).fold(1 % modulus, |d, i| d * rows[i][i] % modulus);
+    if flipped {
+        (modulus - product) % modulus
+    } else {
+        product
+    }
+}
+
+/// Finish what `triangulate` started on the first `columns` columns,
+/// scaling each diagonal entry to one and clearing above it, so the
+/// remaining columns hold the solution.
+///
+/// Fails if a diagonal entry has no inverse.
+pub fn back_substitute(rows: &mut Vec<Vec<u64>>, columns: usize, modulus: u64) -> Option<()> {
+    for c in (0..columns).rev() {
+        let scale = match inverse(rows[c][c], modulus) {
+            Some(scale) => scale,
+            None => return None,
+        };
+        for i in 0..rows[c].len() {
+            rows[c][i] = rows[c][i] * scale % modulus;
+        }
+        for r in 0..c {
+            let q = rows[r][c];
+            for i in 0..rows[r].len() {
+                let sub = q * rows[c][i] % modulus;
+                rows[r][i] = (rows[r][i] + modulus - sub) % modulus;
+            }
+        }
+    }
+    Some(())
+}
+
+/// The matrix which multiplies with `matrix` to give the identity,
+/// modulo `modulus`. There is one exactly when the determinant is
+/// coprime to the modulus.
+pub fn inverse_matrix(matrix: &Matrix, modulus: u64) -> Option<Matrix> {
+    let size = matrix.len();
+    let mut rows: Vec<Vec<u64>> = matrix.iter()
+        .enumerate()
+        .map(|(r, row)| {
+            let mut augmented: Vec<u64> = row.iter().map(|x| x % modulus).collect();
+            augmented.extend((0..size).map(|c| if c == r { 1 } else { 0 }));
+            augmented
+        })
+        .collect();
+    triangulate(&mut rows, size, modulus);
+    if back_substitute(&mut rows, size, modulus).is_none() {
+        return None;
+    }
+    Some(rows.into_iter().map(|row| row[size..].to_vec()).collect())
+}
+
+/// `matrix` times the column `vector`, modulo `modulus`.
+pub fn multiply(matrix: &Matrix, vector: &[u64], modulus: u64) -> Vec<u64> {
+    matrix.iter()
+        .map(|row| row.iter().zip(vector).fold(0, |sum, (a, b)| (sum + a * b) % modulus))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gcd_of_pairs() {
+        assert_eq!(gcd(26, 4), 2);
+        assert_eq!(gcd(5, 26), 1);
+        assert_eq!(gcd(0, 7), 7);
+    }
+
+    #[test]
+    fn inverse_when_coprime() {
+        assert_eq!(inverse(5, 26), Some(21));
+        assert_eq!(inverse(7, 36), Some(31));
+    }
+
+    #[test]
+    fn no_inverse_when_not_coprime() {
+        assert_eq!(inverse(13, 26), None);
+        assert_eq!(inverse(0, 26), None);
+    }
+
+    #[test]
+    fn determinant_mod_26() {
+        let m = vec![vec![6, 24, 1], vec![13, 16, 10], vec![20, 17, 15]];
+        // 441 over the integers
+        assert_eq!(determinant(&m, 26), 25);
+        assert_eq!(determinant(&vec![vec![2, 4], vec![1, 3]], 26), 2);
+        assert_eq!(determinant(&vec![vec![0, 1], vec![1, 0]], 26), 25);
+    }
+
+    #[test]
+    fn inverse_of_matrix() {
+        let m = vec![vec![6, 24, 1], vec![13, 16, 10], vec![20, 17, 15]];
+        let inverse = vec![vec![8, 5, 10], vec![21, 8, 21], vec![21, 12, 8]];
+        assert_eq!(inverse_matrix(&m, 26), Some(inverse));
+    }
+
+    #[test]
+    fn inverse_needs_unit_pivots_found_by_euclid() {
+        // Neither entry of the first column has an inverse mod 26,
+        // but the rows combine to an invertible matrix all the same.
+        let m = vec![vec![2, 1], vec![13, 1]];
+        let inverse = inverse_matrix(&m, 26).unwrap();
+        assert_eq!(multiply(&inverse, &multiply(&m, &[7, 11], 26), 26), vec![7, 11]);
+    }
+
+    #[test]
+    fn singular_matrix_has_no_inverse() {
+        assert_eq!(inverse_matrix(&vec![vec![2, 4], vec![1, 3]], 26), None);
+        assert_eq!(inverse_matrix(&vec![vec![1, 2], vec![2, 4]], 26), None);
+    }
+}
@@ -11,6 +11,79 @@ pub type Result<T> = std::result::Result<T, ConfigError>;
 
 const ALPHABET_KEY: &'static str = "alphabet";
 const MAPPING_KEY: &'static str = "mapping";
+const SUBSTITUTION_KEY: &'static str = "substitution";
+const KEYWORD_KEY: &'static str = "keyword";
+const PERMUTATION_KEY: &'static str = "permutation";
+const HILL_KEY: &'static str = "hill";
+const MATRIX_KEY: &'static str = "matrix";
+const KEY_KEY: &'static str = "key";
+const PADDING_KEY: &'static str = "padding";
+const ENIGMA_KEY: &'static str = "enigma";
+const ROTORS_KEY: &'static str = "rotors";
+const NAME_KEY: &'static str = "name";
+const WIRING_KEY: &'static str = "wiring";
+const NOTCHES_KEY: &'static str = "notches";
+const REFLECTOR_KEY: &'static str = "reflector";
+const RINGS_KEY: &'static str = "rings";
+const POSITIONS_KEY: &'static str = "positions";
+const PLUGBOARD_KEY: &'static str = "plugboard";
+
+/// The ways a substitution alphabet can be written down.
+#[derive(PartialEq, Eq, Debug)]
+pub enum SubstitutionKey {
+    /// Keyword first, then the rest of the alphabet
+    Keyword(String),
+    /// Every character of the alphabet, in their new order
+    Permutation(String),
+}
+
+/// The ways a Hill cipher key can be written down.
+#[derive(PartialEq, Eq, Debug)]
+pub enum HillKey {
+    /// Rows of numbers
+    Matrix(Vec<Vec<u64>>),
+    /// Characters of the alphabet, filling the matrix row by row
+    Text(String),
+}
+
+/// The `[hill]` table: a key, and what to pad the last block with.
+#[derive(PartialEq, Eq, Debug)]
+pub struct HillConfig {
+    pub key: HillKey,
+    pub padding: Option<String>,
+}
+
+/// A rotor of the `[enigma]` table.
+#[derive(PartialEq, Eq, Debug)]
+pub enum RotorSpec {
+    /// One of the historical rotors, such as "I" or "beta"
+    Named(String),
+    /// Where each character of the alphabet is wired to, and the
+    /// positions at which it turns the next rotor over
+    Wired { wiring: String, notches: String },
+}
+
+/// Ring settings or start positions, one for each rotor.
+#[derive(PartialEq, Eq, Debug)]
+pub enum WheelSetting {
+    /// Characters of the alphabet, as in "AAZ"
+    Letters(String),
+    /// Numbers counted from 1, as in [1, 1, 26]
+    Numbers(Vec<u64>),
+}
+
+/// The `[enigma]` table: rotors from left to right, the reflector,
+/// and the optional daily settings.
+#[derive(PartialEq, Eq, Debug)]
+pub struct EnigmaConfig {
+    pub rotors: Vec<RotorSpec>,
+    /// A historical reflector such as "B", or its wiring
+    pub reflector: String,
+    pub rings: Option<WheelSetting>,
+    pub positions: Option<WheelSetting>,
+    /// Pairs of swapped characters separated by spaces, as in "AV BS CG"
+    pub plugboard: Option<String>,
+}
 
 fn string_to_char(s: &String) -> Result<char> {
     match s.len() {
@@ -80,6 +153,203 @@ fn parse_mapping(root_table: &Table) -> Result<Vec<(char, char)>> {
     }
 }
 
+fn string_from_table(table: &Table, key: &str) -> Result<Option<String>> {
+    match table.get(key) {
+        Some(&Value::String(ref s)) => Ok(Some(s.clone())),
+        Some(x) => {
+            Err(ConfigError::SchemaError(format!("Key '{}' did not have String: {:?}", key, x)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Read the optional `[substitution]` table, which must have
+/// exactly one of `keyword` or `permutation`.
+pub fn parse_substitution(root_table: &Table) -> Result<Option<SubstitutionKey>> {
+    let substitution: &Table = match root_table.get(SUBSTITUTION_KEY) {
+        Some(&Value::Table(ref substitution)) => substitution,
+        Some(x) => {
+            return Err(ConfigError::SchemaError(format!("Key '{}' did not have Table: {:?}",
+                                                        SUBSTITUTION_KEY,
+                                                        x)))
+        }
+        None => return Ok(None),
+    };
+
+    let keyword = try!(string_from_table(substitution, KEYWORD_KEY));
+    let permutation = try!(string_from_table(substitution, PERMUTATION_KEY));
+    match (keyword, permutation) {
+        (Some(k), None) => Ok(Some(SubstitutionKey::Keyword(k))),
+        (None, Some(p)) => Ok(Some(SubstitutionKey::Permutation(p))),
+        _ => {
+            Err(ConfigError::SchemaError(format!("Table '{}' needs exactly one of '{}' or '{}'",
+                                                 SUBSTITUTION_KEY,
+                                                 KEYWORD_KEY,
+                                                 PERMUTATION_KEY)))
+        }
+    }
+}
+
+fn parse_matrix(value: &Value) -> Result<Vec<Vec<u64>>> {
+    let schema_error = || {
+        ConfigError::SchemaError(format!("Key '{}' was not a square array of non-negative \
+                                          integers: {:?}",
+                                         MATRIX_KEY,
+                                         value))
+    };
+    let rows: &Array = match *value {
+        Value::Array(ref rows) => rows,
+        _ => return Err(schema_error()),
+    };
+    let mut matrix = Vec::with_capacity(rows.len());
+    for row in rows {
+        let entries = match *row {
+            Value::Array(ref entries) if entries.len() == rows.len() => entries,
+            _ => return Err(schema_error()),
+        };
+        let mut parsed = Vec::with_capacity(entries.len());
+        for entry in entries {
+            match *entry {
+                Value::Integer(x) if x >= 0 => parsed.push(x as u64),
+                _ => return Err(schema_error()),
+            }
+        }
+        matrix.push(parsed);
+    }
+    if matrix.is_empty() {
+        return Err(schema_error());
+    }
+    Ok(matrix)
+}
+
+/// Read the optional `[hill]` table, which must have exactly one of
+/// `matrix` or `key`, and may have `padding`.
+pub fn parse_hill(root_table: &Table) -> Result<Option<HillConfig>> {
+    let hill: &Table = match root_table.get(HILL_KEY) {
+        Some(&Value::Table(ref hill)) => hill,
+        Some(x) => {
+            return Err(ConfigError::SchemaError(format!("Key '{}' did not have Table: {:?}",
+                                                        HILL_KEY,
+                                                        x)))
+        }
+        None => return Ok(None),
+    };
+
+    let text = try!(string_from_table(hill, KEY_KEY));
+    let key = match (hill.get(MATRIX_KEY), text) {
+        (Some(matrix), None) => HillKey::Matrix(try!(parse_matrix(matrix))),
+        (None, Some(text)) => HillKey::Text(text),
+        _ => {
+            return Err(ConfigError::SchemaError(format!("Table '{}' needs exactly one of '{}' \
+                                                         or '{}'",
+                                                        HILL_KEY,
+                                                        MATRIX_KEY,
+                                                        KEY_KEY)))
+        }
+    };
+    Ok(Some(HillConfig {
+        key: key,
+        padding: try!(string_from_table(hill, PADDING_KEY)),
+    }))
+}
+
+fn parse_rotor(value: &Value) -> Result<RotorSpec> {
+    match *value {
+        Value::String(ref name) => Ok(RotorSpec::Named(name.clone())),
+        Value::Table(ref rotor) => {
+            if let Some(name) = try!(string_from_table(rotor, NAME_KEY)) {
+                return Ok(RotorSpec::Named(name));
+            }
+            match try!(string_from_table(rotor, WIRING_KEY)) {
+                Some(wiring) => {
+                    Ok(RotorSpec::Wired {
+                        wiring: wiring,
+                        notches: try!(string_from_table(rotor, NOTCHES_KEY)).unwrap_or_default(),
+                    })
+                }
+                None => {
+                    Err(ConfigError::SchemaError(format!("Rotor table needs a '{}' or '{}': \
+                                                          {:?}",
+                                                         NAME_KEY,
+                                                         WIRING_KEY,
+                                                         rotor)))
+                }
+            }
+        }
+        ref x => {
+            Err(ConfigError::SchemaError(format!("Rotor was not a name or a table: {:?}", x)))
+        }
+    }
+}
+
+fn wheel_setting_from_table(table: &Table, key: &str) -> Result<Option<WheelSetting>> {
+    let schema_error = |x: &Value| {
+        ConfigError::SchemaError(format!("Key '{}' was not a string or an array of \
+                                          positive integers: {:?}",
+                                         key,
+                                         x))
+    };
+    match table.get(key) {
+        Some(&Value::String(ref s)) => Ok(Some(WheelSetting::Letters(s.clone()))),
+        Some(&Value::Array(ref numbers)) => {
+            let mut parsed = Vec::with_capacity(numbers.len());
+            for n in numbers {
+                match *n {
+                    Value::Integer(x) if x >= 1 => parsed.push(x as u64),
+                    _ => return Err(schema_error(&Value::Array(numbers.clone()))),
+                }
+            }
+            Ok(Some(WheelSetting::Numbers(parsed)))
+        }
+        Some(x) => Err(schema_error(x)),
+        None => Ok(None),
+    }
+}
+
+/// Read the optional `[enigma]` table, which must have `rotors` and a
+/// `reflector`, and may have `rings`, `positions` and a `plugboard`.
+/// Rotors are either an array of names, or `[[enigma.rotors]]` tables
+/// that each have a `name` or their own `wiring` and `notches`.
+pub fn parse_enigma(root_table: &Table) -> Result<Option<EnigmaConfig>> {
+    let enigma: &Table = match root_table.get(ENIGMA_KEY) {
+        Some(&Value::Table(ref enigma)) => enigma,
+        Some(x) => {
+            return Err(ConfigError::SchemaError(format!("Key '{}' did not have Table: {:?}",
+                                                        ENIGMA_KEY,
+                                                        x)))
+        }
+        None => return Ok(None),
+    };
+
+    let rotors: Vec<RotorSpec> = match enigma.get(ROTORS_KEY) {
+        Some(&Value::Array(ref rotors)) if !rotors.is_empty() => {
+            try!(rotors.iter().map(parse_rotor).collect())
+        }
+        x => {
+            return Err(ConfigError::SchemaError(format!("Table '{}' needs a non-empty array \
+                                                         of '{}': {:?}",
+                                                        ENIGMA_KEY,
+                                                        ROTORS_KEY,
+                                                        x)))
+        }
+    };
+    let reflector = match try!(string_from_table(enigma, REFLECTOR_KEY)) {
+        Some(reflector) => reflector,
+        None => {
+            return Err(ConfigError::SchemaError(format!("Table '{}' needs a '{}'",
+                                                        ENIGMA_KEY,
+                                                        REFLECTOR_KEY)))
+        }
+    };
+    Ok(Some(EnigmaConfig {
+        rotors: rotors,
+        reflector: reflector,
+        rings: try!(wheel_setting_from_table(enigma, RINGS_KEY)),
+        positions: try!(wheel_setting_from_table(enigma, POSITIONS_KEY)),
+        plugboard: try!(string_from_table(enigma, PLUGBOARD_KEY)),
+    }))
+}
+
 pub fn new_from_toml(root_table: Table) -> super::Result<Encoding> {
     let mut new_encoding = Encoding::new();
     trace!("Root Table: {:?}", root_table);
@@ -111,6 +381,8 @@ mod tests {
     use super::super::Error;
     use super::super::error::ConfigError;
     use super::super::Encoding;
+    use super::{parse_enigma, parse_hill, parse_substitution, read_toml_string, EnigmaConfig,
+                HillConfig, HillKey, RotorSpec, SubstitutionKey, WheelSetting};
 
     #[test]
     fn fail_to_parse_bad_toml() {
@@ -150,5 +422,122 @@ mod tests {
 
     }
 
+    #[test]
+    fn parse_substitution_keyword() {
+        let test_string = r#"
+        alphabet = ["a", "b", "c"]
+        [substitution]
+        keyword = "cab"
+        "#;
+
+        let root = read_toml_string(test_string).unwrap();
+        assert_eq!(parse_substitution(&root).unwrap(),
+                   Some(SubstitutionKey::Keyword("cab".to_string())));
+    }
+
+    #[test]
+    fn parse_missing_substitution() {
+        let root = read_toml_string(r#"alphabet = ["a"]"#).unwrap();
+        assert_eq!(parse_substitution(&root).unwrap(), None);
+    }
+
+    #[test]
+    fn fail_to_parse_ambiguous_substitution() {
+        let test_string = r#"
+        [substitution]
+        keyword = "cab"
+        permutation = "cab"
+        "#;
+
+        let root = read_toml_string(test_string).unwrap();
+        match parse_substitution(&root) {
+            Err(ConfigError::SchemaError(_)) => (),
+            Ok(_) => panic!("We parsed an ambiguous substitution!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn parse_hill_matrix() {
+        let test_string = r#"
+        [hill]
+        matrix = [[3, 3], [2, 5]]
+        padding = "z"
+        "#;
+
+        let root = read_toml_string(test_string).unwrap();
+        assert_eq!(parse_hill(&root).unwrap(),
+                   Some(HillConfig {
+                       key: HillKey::Matrix(vec![vec![3, 3], vec![2, 5]]),
+                       padding: Some("z".to_string()),
+                   }));
+    }
+
+    #[test]
+    fn fail_to_parse_ragged_hill_matrix() {
+        let test_string = r#"
+        [hill]
+        matrix = [[3, 3], [2]]
+        "#;
+
+        let root = read_toml_string(test_string).unwrap();
+        match parse_hill(&root) {
+            Err(ConfigError::SchemaError(_)) => (),
+            Ok(_) => panic!("We parsed a matrix that is not square!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn parse_enigma_table() {
+        let test_string = r#"
+        [enigma]
+        reflector = "B"
+        rings = [2, 21, 12]
+        positions = "BLA"
+        plugboard = "AV BS CG"
+
+        [[enigma.rotors]]
+        name = "II"
+
+        [[enigma.rotors]]
+        name = "IV"
+
+        [[enigma.rotors]]
+        wiring = "VZBRGITYUPSDNHLXAWMJQOFECK"
+        notches = "Z"
+        "#;
+
+        let root = read_toml_string(test_string).unwrap();
+        assert_eq!(parse_enigma(&root).unwrap(),
+                   Some(EnigmaConfig {
+                       rotors: vec![RotorSpec::Named("II".to_string()),
+                                    RotorSpec::Named("IV".to_string()),
+                                    RotorSpec::Wired {
+                                        wiring: "VZBRGITYUPSDNHLXAWMJQOFECK".to_string(),
+                                        notches: "Z".to_string(),
+                                    }],
+                       reflector: "B".to_string(),
+                       rings: Some(WheelSetting::Numbers(vec![2, 21, 12])),
+                       positions: Some(WheelSetting::Letters("BLA".to_string())),
+                       plugboard: Some("AV BS CG".to_string()),
+                   }));
+    }
+
+    #[test]
+    fn fail_to_parse_enigma_without_reflector() {
+        let test_string = r#"
+        [enigma]
+        rotors = ["I", "II", "III"]
+        "#;
+
+        let root = read_toml_string(test_string).unwrap();
+        match parse_enigma(&root) {
+            Err(ConfigError::SchemaError(_)) => (),
+            Ok(_) => panic!("We parsed an Enigma with no reflector!"),
+            Err(e) => panic!("We failed with the wrong type of error {:?}", e),
+        }
+    }
+
     // TODO: Test all the various types of errors that we throw
 }
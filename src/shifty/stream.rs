@@ -0,0 +1,152 @@
+//! Incremental UTF-8 decoding, so messages can be transformed
+//! without ever holding the whole thing in memory.
+
+use std::io::{self, Read};
+use std::str;
+use super::Result;
+
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// Iterator over the chars of a reader.
+///
+/// Multi-byte chars that straddle two reads are carried over
+/// to the next buffer rather than being treated as invalid.
+pub struct Chars<R> {
+    inner: R,
+    buf: Vec<u8>,
+    start: usize,
+    end: usize,
+    eof: bool,
+}
+
+impl<R: Read> Chars<R> {
+    pub fn new(inner: R) -> Chars<R> {
+        Chars {
+            inner: inner,
+            buf: vec![0; BUFFER_SIZE],
+            start: 0,
+            end: 0,
+            eof: false,
+        }
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        // At most three bytes of a partial char are left behind.
+        let leftover = self.end - self.start;
+        for i in 0..leftover {
+            self.buf[i] = self.buf[self.start + i];
+        }
+        self.start = 0;
+        self.end = leftover;
+
+        loop {
+            match self.inner.read(&mut self.buf[self.end..]) {
+                Ok(0) => {
+                    self.eof = true;
+                    return Ok(());
+                }
+                Ok(n) => {
+                    self.end += n;
+                    return Ok(());
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Number of bytes in the UTF-8 sequence starting with `first`,
+/// or zero if `first` can not start a sequence.
+fn utf8_width(first: u8) -> usize {
+    match first {
+        0x00...0x7F => 1,
+        0xC0...0xDF => 2,
+        0xE0...0xEF => 3,
+        0xF0...0xF7 => 4,
+        _ => 0,
+    }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8")
+}
+
+impl<R: Read> Iterator for Chars<R> {
+    type Item = Result<char>;
+
+    fn next(&mut self) -> Option<Result<char>> {
+        loop {
+            let available = self.end - self.start;
+            if available > 0 {
+                let width = utf8_width(self.buf[self.start]);
+                if width == 0 {
+                    self.start = self.end;
+                    return Some(Err(From::from(invalid_utf8())));
+                }
+                if available >= width {
+                    let bytes = &self.buf[self.start..self.start + width];
+                    self.start += width;
+                    return match str::from_utf8(bytes) {
+                        Ok(s) => Some(Ok(s.chars().next().unwrap())),
+                        Err(_) => Some(Err(From::from(invalid_utf8()))),
+                    };
+                }
+            }
+
+            if self.eof {
+                if available > 0 {
+                    self.start = self.end;
+                    return Some(Err(From::from(invalid_utf8())));
+                }
+                return None;
+            }
+
+            if let Err(e) = self.fill() {
+                return Some(Err(From::from(e)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+    use super::Chars;
+
+    /// Hands out its bytes a few at a time, to split chars across reads.
+    struct Trickle<'a> {
+        bytes: &'a [u8],
+        step: usize,
+    }
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = *[self.step, buf.len(), self.bytes.len()].iter().min().unwrap();
+            buf[..n].copy_from_slice(&self.bytes[..n]);
+            self.bytes = &self.bytes[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn decode_across_reads() {
+        let text = "añb€c𝄞d";
+        for step in 1..5 {
+            let reader = Trickle {
+                bytes: text.as_bytes(),
+                step: step,
+            };
+            let decoded: String = Chars::new(reader).map(|c| c.unwrap()).collect();
+            assert_eq!(decoded, text);
+        }
+    }
+
+    #[test]
+    fn truncated_char_is_an_error() {
+        let bytes = &"€".as_bytes()[..2];
+        let mut chars = Chars::new(bytes);
+        assert!(chars.next().unwrap().is_err());
+        assert!(chars.next().is_none());
+    }
+}
@@ -1,6 +1,8 @@
 use std::path::Path;
 use std::error::Error;
+use std::cmp::min;
 use std::fs::File;
+use std::io;
 use std::io::prelude::*;
 use super::Result;
 
@@ -27,3 +29,135 @@ pub fn read_path(raw_path: &str) -> Result<String> {
     }
     Ok(s.trim().to_string())
 }
+
+pub fn create_path(raw_path: &str) -> Result<File> {
+    let path = Path::new(raw_path);
+    match File::create(&path) {
+        Err(why) => {
+            error!("couldn't create {}: {}", path.display(), why.description());
+            Err(box why)
+        }
+        Ok(file) => Ok(file),
+    }
+}
+
+pub fn open_path(raw_path: &str) -> Result<File> {
+    let path = Path::new(raw_path);
+    match File::open(&path) {
+        Err(why) => {
+            error!("couldn't open {}: {}", path.display(), why.description());
+            Err(box why)
+        }
+        Ok(file) => Ok(file),
+    }
+}
+
+/// Reader which drops a single line ending from the very end of its input.
+///
+/// This is the streaming version of the `trim` we do in `read_path`,
+/// so a file saved with a trailing newline transcodes the same either way.
+/// Line ending bytes are held back until we know whether more data follows.
+/// Once the input is exhausted, `line_ending` says what was dropped.
+pub struct Chomp<R> {
+    inner: R,
+    ready: Vec<u8>,
+    position: usize,
+    held: Vec<u8>,
+    removed: Vec<u8>,
+    eof: bool,
+}
+
+impl<R: Read> Chomp<R> {
+    pub fn new(inner: R) -> Chomp<R> {
+        Chomp {
+            inner: inner,
+            ready: Vec::new(),
+            position: 0,
+            held: Vec::new(),
+            removed: Vec::new(),
+            eof: false,
+        }
+    }
+
+    /// The line ending dropped from the end of the input, if any.
+    pub fn line_ending(&self) -> &[u8] {
+        &self.removed
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0; 8 * 1024];
+        let n = try!(self.inner.read(&mut chunk));
+        self.ready.clear();
+        self.position = 0;
+
+        if n == 0 {
+            self.eof = true;
+            let len = self.held.len();
+            if self.held.ends_with(b"\r\n") {
+                self.removed = self.held.split_off(len - 2);
+            } else if self.held.ends_with(b"\n") {
+                self.removed = self.held.split_off(len - 1);
+            }
+            self.ready.append(&mut self.held);
+            return Ok(());
+        }
+
+        self.ready.append(&mut self.held);
+        self.ready.extend_from_slice(&chunk[..n]);
+        let line_ending = self.ready
+            .iter()
+            .rev()
+            .take_while(|b| **b == b'\n' || **b == b'\r')
+            .count();
+        let keep = self.ready.len() - line_ending;
+        self.held = self.ready.split_off(keep);
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Chomp<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.ready.len() {
+            if self.eof {
+                return Ok(0);
+            }
+            try!(self.fill());
+        }
+        let n = min(buf.len(), self.ready.len() - self.position);
+        buf[..n].copy_from_slice(&self.ready[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use super::Chomp;
+
+    fn chomp(s: &str) -> String {
+        let mut out = String::new();
+        Chomp::new(s.as_bytes()).read_to_string(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn chomp_remembers_line_ending() {
+        for s in &["abc", "abc\n", "abc\r\n", "abc\n\n"] {
+            let mut reader = Chomp::new(s.as_bytes());
+            let mut out = Vec::new();
+            reader.read_to_end(&mut out).unwrap();
+            out.extend_from_slice(reader.line_ending());
+            assert_eq!(out, s.as_bytes());
+        }
+    }
+
+    #[test]
+    fn chomp_single_line_ending() {
+        assert_eq!(chomp("abc\n"), "abc");
+        assert_eq!(chomp("abc\r\n"), "abc");
+        assert_eq!(chomp("abc\n\n"), "abc\n");
+        assert_eq!(chomp("a\nb\nc"), "a\nb\nc");
+        assert_eq!(chomp(""), "");
+    }
+}